@@ -1,4 +1,13 @@
-use crate::types::AudioBuffer;
+use core::f64;
+use std::ops::Range;
+use clap::ValueEnum;
+
+use crate::{parse_utils::keyword_variant, types::AudioBuffer};
+
+/// RMS level under which audio counts as silence (-80 dB)
+pub const SILENCE_RMS: f64 = 0.0001;
+/// Length of the windows silence is measured over
+const SILENCE_WINDOW_SECONDS: f64 = 0.01;
 
 pub fn sanitize_buffer(buffer: &mut AudioBuffer) -> Result<(), String> {
 
@@ -30,4 +39,97 @@ pub fn sanitize_buffer(buffer: &mut AudioBuffer) -> Result<(), String> {
 
 pub fn get_buffer_duration(buffer: &AudioBuffer) -> f64 {
     buffer.channels[0].len() as f64 / buffer.spec.sample_rate as f64
-}
\ No newline at end of file
+}
+
+/// Rounds to the nearest of `levels` steps per unit of full scale, within [-1, 1]
+pub fn quantize(x: f64, levels: f64) -> f64 {
+    ((x * levels).round() / levels).clamp(-1.0, 1.0)
+}
+
+/// Shape of fades and crossfades
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FadeCurve {
+    Linear,
+    /// Constant power when crossfading uncorrelated signals
+    #[value(name = "equalpower")]
+    EqualPower,
+    /// Linear in decibels, from -60 dB
+    Logarithmic,
+    /// Starts and ends slowly
+    #[value(name = "scurve")]
+    SCurve,
+}
+
+impl FadeCurve {
+    pub const KEYWORDS: &'static [&'static str] = &["linear", "equalpower", "logarithmic", "scurve"];
+    const VARIANTS: [FadeCurve; 4] = [FadeCurve::Linear, FadeCurve::EqualPower, FadeCurve::Logarithmic, FadeCurve::SCurve];
+    const LOG_FLOOR: f64 = 0.001;
+
+    /// Keyword arguments hold the index of their keyword
+    pub fn from_argument(value: f64) -> Result<FadeCurve, String> {
+        keyword_variant(value, FadeCurve::KEYWORDS, &FadeCurve::VARIANTS)
+    }
+
+    /// Gain of a fade in at `position`, from 0 (silent) to 1 (full level)
+    pub fn gain(&self, position: f64) -> f64 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => position,
+            FadeCurve::EqualPower => (position * f64::consts::FRAC_PI_2).sin(),
+            FadeCurve::Logarithmic => {
+                let gain = FadeCurve::LOG_FLOOR.powf(1.0 - position);
+                (gain - FadeCurve::LOG_FLOOR) / (1.0 - FadeCurve::LOG_FLOOR)
+            },
+            FadeCurve::SCurve => 0.5 - 0.5 * (position * f64::consts::PI).cos(),
+        }
+    }
+}
+
+/// Appends `next` to `buffer`, crossfading the last `overlap` samples of one with the first of the other.
+/// The overlap is shortened when either signal is too short.
+pub fn join(buffer: &mut AudioBuffer, next: &AudioBuffer, overlap: usize, curve: FadeCurve) -> Result<(), String> {
+    if buffer.channels.len() != next.channels.len() {
+        return Err(format!("Can't join signals with {} and {} channels", buffer.channels.len(), next.channels.len()));
+    }
+
+    let overlap = overlap.min(buffer.channels[0].len()).min(next.channels[0].len());
+    for (channel, next_channel) in buffer.channels.iter_mut().zip(next.channels.iter()) {
+        let start = channel.len() - overlap;
+        for (n, next_sample) in next_channel[..overlap].iter().enumerate() {
+            let position = (n + 1) as f64 / (overlap + 1) as f64;
+            channel[start + n] = channel[start + n] * curve.gain(1.0 - position) + next_sample * curve.gain(position);
+        }
+        channel.extend_from_slice(&next_channel[overlap..]);
+    }
+
+    Ok(())
+}
+
+/// RMS of all channels over a range of samples
+pub fn window_rms(channels: &[Vec<f64>], range: Range<usize>) -> f64 {
+    let count = (range.len() * channels.len()) as f64;
+    let square_sum: f64 = channels.iter().map(|channel| channel[range.clone()].iter().map(|s| s * s).sum::<f64>()).sum();
+    (square_sum / count).sqrt()
+}
+
+/// Ranges of samples that aren't silent. Audio is measured in short windows against the RMS threshold,
+/// and silent gaps shorter than `min_silence` samples don't split a region.
+pub fn find_sound(buffer: &AudioBuffer, threshold_rms: f64, min_silence: usize) -> Vec<Range<usize>> {
+    let length = buffer.channels[0].len();
+    let window = ((SILENCE_WINDOW_SECONDS * buffer.spec.sample_rate as f64) as usize).max(1);
+
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    for start in (0..length).step_by(window) {
+        let end = (start + window).min(length);
+        if window_rms(&buffer.channels, start..end) < threshold_rms {
+            continue;
+        }
+
+        match regions.last_mut() {
+            Some(last) if start - last.end < min_silence.max(1) => last.end = end,
+            _ => regions.push(start..end),
+        }
+    }
+
+    regions
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::types::{AudioBuffer, AudioEffect, Automation, Envelope, TimeValue};
+
+/// Samples between updates of arguments that are automated per block, like filter coefficients
+pub const AUTOMATION_BLOCK: usize = 32;
+
+/// Next to -inf, values are interpolated as amplitudes so fades from and to silence stay smooth
+fn interpolate(from: f64, to: f64, position: f64) -> f64 {
+    if from.is_infinite() || to.is_infinite() {
+        let (from, to) = (10.0_f64.powf(from / 20.0), 10.0_f64.powf(to / 20.0));
+        return 20.0 * (from + (to - from) * position).log10();
+    }
+    from + (to - from) * position
+}
+
+/// Values of the envelope for `length` samples, starting `offset` samples into the signal.
+/// The first and last values are held before and after the breakpoints, which are in chronological order.
+pub fn render(envelope: &Envelope, offset: usize, length: usize, sample_rate: u32) -> Vec<f64> {
+    let points: Vec<(f64, f64)> = envelope.points.iter().map(|(time, value)| match time {
+        TimeValue::Seconds(seconds) => (seconds * sample_rate as f64, *value),
+        TimeValue::Samples(samples) => (*samples, *value),
+    }).collect();
+
+    // Index of the first breakpoint after the current sample
+    let mut next = 0;
+    (offset..offset + length).map(|n| {
+        let time = n as f64;
+        while next < points.len() && points[next].0 <= time {
+            next += 1;
+        }
+        match next {
+            0 => points[0].1,
+            next if next == points.len() => points[next - 1].1,
+            next => {
+                let ((start, from), (end, to)) = (points[next - 1], points[next]);
+                interpolate(from, to, (time - start) / (end - start))
+            },
+        }
+    }).collect()
+}
+
+pub fn render_all(envelopes: &HashMap<String, Envelope>, offset: usize, length: usize, sample_rate: u32) -> Automation {
+    envelopes.iter()
+        .map(|(argument, envelope)| (argument.clone(), render(envelope, offset, length, sample_rate)))
+        .collect()
+}
+
+/// Reads an argument per sample. Automated arguments keep their last value past the end of their curve, like in tails.
+pub fn argument_values<'a>(argument: &str, arguments: &'a HashMap<String, f64>, automation: &'a Automation) -> impl Fn(usize) -> f64 + 'a {
+    let fixed = *arguments.get(argument).unwrap();
+    let curve = automation.get(argument).map(|curve| curve.as_slice()).unwrap_or(&[]);
+    move |n| curve.get(n).or(curve.last()).copied().unwrap_or(fixed)
+}
+
+/// The largest value an argument takes, for sizing buffers like delay lines
+pub fn argument_max(argument: &str, arguments: &HashMap<String, f64>, automation: &Automation) -> f64 {
+    let fixed = *arguments.get(argument).unwrap();
+    automation.get(argument).map_or(fixed, |curve| curve.iter().fold(fixed, |a, b| a.max(*b)))
+}
+
+/// The arguments with every automated one set to its value at a sample
+pub fn arguments_at(n: usize, arguments: &HashMap<String, f64>, automation: &Automation) -> HashMap<String, f64> {
+    let mut values = arguments.clone();
+    for (argument, curve) in automation.iter() {
+        if let Some(value) = curve.get(n).or(curve.last()) {
+            values.insert(argument.clone(), *value);
+        }
+    }
+    values
+}
+
+/// Applies an effect block by block, each with the values of the automated arguments at its first sample.
+/// This only suits effects that don't carry anything over from one sample to the next, as every block starts afresh.
+pub fn apply_in_blocks<E: AudioEffect + ?Sized>(effect: &E, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+    if automation.is_empty() {
+        return effect.apply_effect(buffer, arguments, tail_length);
+    }
+
+    let length = buffer.channels[0].len();
+    let mut channels: Vec<Vec<f64>> = Vec::new();
+    let mut message = None;
+    for start in (0..length).step_by(AUTOMATION_BLOCK) {
+        let end = (start + AUTOMATION_BLOCK).min(length);
+        let mut block = AudioBuffer { spec: buffer.spec, channels: buffer.channels.iter().map(|channel| channel[start..end].to_vec()).collect() };
+        message = effect.apply_effect(&mut block, &arguments_at(start, arguments, automation), tail_length)?.or(message);
+
+        // Effects may change the channel count, but not the length
+        channels.resize_with(block.channels.len(), || Vec::with_capacity(length));
+        for (channel, processed) in channels.iter_mut().zip(block.channels.iter()) {
+            channel.extend_from_slice(processed);
+        }
+        buffer.spec = block.spec;
+    }
+
+    buffer.channels = channels;
+    Ok(message)
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::{automation::{self, AUTOMATION_BLOCK}, types::{Automation, AudioBuffer, BiquadCoefficients}};
+
+/// Biquad filter in transposed direct form II.
+/// Coefficients are normalized by a0 once, and every channel keeps its own state between calls,
+/// so a signal can be processed in blocks.
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    states: Vec<[f64; 2]>,
+}
+
+impl Biquad {
+    pub fn new(coefficients: &BiquadCoefficients, channels: usize) -> Biquad {
+        let mut biquad = Biquad { b0: 0.0, b1: 0.0, b2: 0.0, a1: 0.0, a2: 0.0, states: vec![[0.0; 2]; channels] };
+        biquad.set_coefficients(coefficients);
+        biquad
+    }
+
+    /// Replaces the coefficients while keeping the state, so the filter can be modulated
+    pub fn set_coefficients(&mut self, coefficients: &BiquadCoefficients) {
+        let a0 = coefficients.a0;
+        self.b0 = coefficients.b0 / a0;
+        self.b1 = coefficients.b1 / a0;
+        self.b2 = coefficients.b2 / a0;
+        self.a1 = coefficients.a1 / a0;
+        self.a2 = coefficients.a2 / a0;
+    }
+
+    pub fn process_sample(&mut self, channel: usize, x: f64) -> f64 {
+        let state = &mut self.states[channel];
+        let y = self.b0 * x + state[0];
+        state[0] = self.b1 * x - self.a1 * y + state[1];
+        state[1] = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Second order sections in series, every sample goes through all of them before the next one
+pub struct BiquadCascade {
+    sections: Vec<Biquad>,
+}
+
+impl BiquadCascade {
+    pub fn new(sections: &[BiquadCoefficients], channels: usize) -> BiquadCascade {
+        BiquadCascade { sections: sections.iter().map(|c| Biquad::new(c, channels)).collect() }
+    }
+
+    /// Replaces the coefficients of every section, the amount of sections must stay the same
+    pub fn set_coefficients(&mut self, sections: &[BiquadCoefficients]) {
+        for (section, coefficients) in self.sections.iter_mut().zip(sections.iter()) {
+            section.set_coefficients(coefficients);
+        }
+    }
+
+    pub fn process_sample(&mut self, channel: usize, x: f64) -> f64 {
+        self.sections.iter_mut().fold(x, |value, section| section.process_sample(channel, value))
+    }
+
+    pub fn process_block(&mut self, channel: usize, block: &mut [f64]) {
+        for sample in block.iter_mut() {
+            *sample = self.process_sample(channel, *sample);
+        }
+    }
+
+    pub fn process_buffer(&mut self, buffer: &mut AudioBuffer) {
+        for (index, channel) in buffer.channels.iter_mut().enumerate() {
+            self.process_block(index, channel);
+        }
+    }
+}
+
+/// Filters a whole buffer with fresh state
+pub fn apply_biquads(buffer: &mut AudioBuffer, sections: &[BiquadCoefficients]) {
+    BiquadCascade::new(sections, buffer.channels.len()).process_buffer(buffer);
+}
+
+/// Filters a whole buffer with sections designed from the arguments. When some are automated,
+/// the sections are designed again every block from the values at its start.
+pub fn apply_automated_biquads<F>(buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, design: F)
+where F: Fn(&HashMap<String, f64>) -> Vec<BiquadCoefficients> {
+    if automation.is_empty() {
+        apply_biquads(buffer, &design(arguments));
+        return;
+    }
+
+    let length = buffer.channels[0].len();
+    let mut cascade = BiquadCascade::new(&design(arguments), buffer.channels.len());
+    for start in (0..length).step_by(AUTOMATION_BLOCK) {
+        let end = (start + AUTOMATION_BLOCK).min(length);
+        cascade.set_coefficients(&design(&automation::arguments_at(start, arguments, automation)));
+        for (index, channel) in buffer.channels.iter_mut().enumerate() {
+            cascade.process_block(index, &mut channel[start..end]);
+        }
+    }
+}
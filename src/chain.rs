@@ -0,0 +1,395 @@
+use std::{collections::HashMap, time::Duration};
+use clap::error::ErrorKind;
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::{audio_utils, automation, channel_target, multiband, parse_utils::{self, SAMPLES_SUFFIX, verify_range}, region, routing::{self, BusUsage, Buses}, types::{AudioBuffer, AudioEffect, ChainNode, ChannelTarget, EffectSpec, TimeValue}};
+
+pub type EffectMap = HashMap<String, Box<dyn AudioEffect>>;
+
+/// Dry/wet argument every effect accepts
+pub const MIX_ARG: &str = "mix";
+
+/// Moves the `mix` argument out of the effect arguments
+fn take_mix(effect_spec: &mut EffectSpec) -> Result<(), String> {
+    if effect_spec.envelopes.contains_key(MIX_ARG) {
+        return Err(format!("Argument '{MIX_ARG}' can't be automated"));
+    }
+    if effect_spec.arguments.contains_key(MIX_ARG) {
+        effect_spec.mix = Some(verify_range(&MIX_ARG.to_string(), 0.0, 1.0, &effect_spec.arguments)?);
+        effect_spec.arguments.remove(MIX_ARG);
+    }
+
+    Ok(())
+}
+
+/// Crossfades the processed buffer with the dry signal, after moving it back by the effect latency.
+/// Tails longer than the dry signal are kept.
+fn mix_dry(buffer: &mut AudioBuffer, dry: &AudioBuffer, mix: f64, latency: usize) -> Result<(), String> {
+    if buffer.channels.len() != dry.channels.len() {
+        return Err(format!("Argument '{MIX_ARG}' can't be used when the effect changes the amount of channels"));
+    }
+
+    for (channel, dry_channel) in buffer.channels.iter_mut().zip(dry.channels.iter()) {
+        channel.drain(..latency.min(channel.len()));
+        let length = channel.len().max(dry_channel.len());
+        channel.resize(length, 0.0);
+        for (sample, dry_sample) in channel.iter_mut().zip(dry_channel.iter()) {
+            *sample = dry_sample * (1.0 - mix) + *sample * mix;
+        }
+        for sample in channel.iter_mut().skip(dry_channel.len()) {
+            *sample *= mix;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keyword arguments only accept their words, so the indices passed to effects are always valid
+fn resolve_keywords(effect: &dyn AudioEffect, effect_spec: &mut EffectSpec) -> Result<(), String> {
+    let numeric = effect_spec.arguments.keys().chain(effect_spec.envelopes.keys())
+        .find_map(|argument| effect.get_keywords(argument).map(|choices| (argument, choices)));
+    if let Some((argument, choices)) = numeric {
+        return Err(format!("Argument '{argument}' must be one of {}", choices.join(", ")));
+    }
+
+    for (argument, word) in effect_spec.keywords.iter() {
+        let Some(choices) = effect.get_keywords(argument) else {
+            return Err(format!("Argument '{argument}' must be a number"));
+        };
+
+        match choices.iter().position(|choice| choice == word) {
+            Some(index) => { effect_spec.arguments.insert(argument.clone(), index as f64); },
+            None => return Err(format!("Argument '{argument}' must be one of {}", choices.join(", ")))
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores times given in seconds like plain numbers and times given in samples under their own key
+fn resolve_times(effect: &dyn AudioEffect, effect_spec: &mut EffectSpec) -> Result<(), String> {
+    for (argument, time) in effect_spec.times.iter() {
+        if !effect.get_time_arguments().contains(&argument.as_str()) {
+            return Err(format!("Argument '{argument}' doesn't accept a time unit"));
+        }
+
+        match time {
+            TimeValue::Seconds(seconds) => { effect_spec.arguments.insert(argument.clone(), *seconds); },
+            TimeValue::Samples(samples) => { effect_spec.arguments.insert(format!("{argument}{SAMPLES_SUFFIX}"), *samples); },
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets automated arguments to their first breakpoint, so effects can be validated like with static arguments
+fn resolve_envelopes(effect: &dyn AudioEffect, effect_spec: &mut EffectSpec) -> Result<(), String> {
+    for (argument, envelope) in effect_spec.envelopes.iter() {
+        if !effect.is_automatable(argument) {
+            return Err(format!("Argument '{argument}' can't be automated"));
+        }
+        effect_spec.arguments.insert(argument.clone(), envelope.points[0].1);
+    }
+
+    Ok(())
+}
+
+/// Validates the effect with every breakpoint of its envelopes, interpolated values are always between two of them
+fn validate_breakpoints(effect: &dyn AudioEffect, effect_spec: &EffectSpec, tail_length: &Option<f64>) -> Result<(), String> {
+    for (argument, envelope) in effect_spec.envelopes.iter() {
+        for (_, value) in envelope.points.iter() {
+            let mut arguments = effect_spec.arguments.clone();
+            arguments.insert(argument.clone(), *value);
+            effect.validate_arguments(&arguments, tail_length).map_err(|message| format!("{message} (envelope of '{argument}')"))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_chain(effect_chain: &mut [ChainNode], effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut BusUsage) -> Result<(), (String, ErrorKind)> {
+    for node in effect_chain.iter_mut() {
+        match node {
+            ChainNode::Effect(effect_spec) | ChainNode::Group(effect_spec, _) if routing::is_routing(effect_spec) && (effect_spec.target.is_some() || effect_spec.region.is_some()) => {
+                return Err((format!("{} -> '@' modifiers and regions can't be used on sends and returns", effect_spec.name), ErrorKind::InvalidValue));
+            },
+            ChainNode::Effect(effect_spec) if effect_spec.name == routing::SEND_NAME => {
+                let result = routing::send_gain(effect_spec).and_then(|_| routing::bus_name(effect_spec)).and_then(|bus| buses.send(&bus));
+                if let Err(message) = result {
+                    return Err((format!("{} -> {message}", routing::SEND_NAME), ErrorKind::InvalidValue));
+                }
+            },
+            ChainNode::Effect(effect_spec) if effect_spec.name == routing::RETURN_NAME => {
+                if let Err(message) = routing::bus_name(effect_spec).and_then(|bus| buses.receive(&bus)) {
+                    return Err((format!("{} -> {message}", routing::RETURN_NAME), ErrorKind::InvalidValue));
+                }
+            },
+            ChainNode::Effect(effect_spec) => validate_effect(effect_spec, effect_map, tail_length)?,
+            ChainNode::Group(effect_spec, branches) if effect_spec.name == routing::RETURN_NAME => {
+                // The bus is marked as returned first, so sending to it from its own chain is a loop
+                let result = routing::bus_name(effect_spec).and_then(|bus| buses.receive(&bus)).and_then(|_| match branches.len() {
+                    1 => Ok(()),
+                    _ => Err("Expected a single chain for the bus, without '|'".to_string()),
+                });
+                if let Err(message) = result {
+                    return Err((format!("{} -> {message}", routing::RETURN_NAME), ErrorKind::InvalidValue));
+                }
+                validate_chain(&mut branches[0], effect_map, tail_length, buses)?;
+            },
+            ChainNode::Group(effect_spec, branches) => {
+                if effect_spec.name != multiband::NAME {
+                    return Err((format!("'{}' can't be followed by a group of chains", effect_spec.name), ErrorKind::InvalidValue));
+                }
+                if let Err(message) = take_mix(effect_spec).and_then(|_| multiband::crossovers(effect_spec, branches.len())) {
+                    return Err((format!("{} -> {message}", multiband::NAME), ErrorKind::InvalidValue));
+                }
+                for branch in branches.iter_mut() {
+                    validate_chain(branch, effect_map, tail_length, buses)?;
+                }
+            },
+            ChainNode::Parallel(branches) => {
+                for branch in branches.iter_mut() {
+                    validate_chain(branch, effect_map, tail_length, buses)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_effect(effect_spec: &mut EffectSpec, effect_map: &EffectMap, tail_length: &Option<f64>) -> Result<(), (String, ErrorKind)> {
+    if effect_spec.name == multiband::NAME {
+        return Err((format!("{} -> Expected a group of band chains like '[ a | b ]'", multiband::NAME), ErrorKind::InvalidValue));
+    }
+
+    match effect_map.get(&effect_spec.name) {
+        Some(effect) => {
+            if let Err(message) = resolve_keywords(effect.as_ref(), effect_spec)
+                .and_then(|_| resolve_times(effect.as_ref(), effect_spec))
+                .and_then(|_| take_mix(effect_spec))
+                .and_then(|_| match (effect_spec.mix.is_some(), effect_spec.target.is_some(), effect.changes_duration()) {
+                    (true, _, true) => Err(format!("Argument '{MIX_ARG}' can't be used on effects that change the duration")),
+                    (_, true, true) => Err("'@' modifiers can't be used on effects that change the duration".to_string()),
+                    _ => Ok(()),
+                })
+                .and_then(|_| resolve_envelopes(effect.as_ref(), effect_spec)) {
+                return Err((format!("{} -> {message}", effect.get_name()), ErrorKind::InvalidValue));
+            }
+            if let Err(message) = effect.validate_arguments(&effect_spec.arguments, tail_length)
+                .and_then(|_| validate_breakpoints(effect.as_ref(), effect_spec, tail_length)) {
+                return Err((format!("{} -> {message}", effect.get_name()), ErrorKind::InvalidValue));
+            }
+            Ok(())
+        },
+        None => Err((format!("Unknown effect '{}'", effect_spec.name), ErrorKind::UnknownArgument))
+    }
+}
+
+/// Parses the effect arguments and validates every effect before any audio is touched
+pub fn prepare_chain(effects: &[String], effect_map: &EffectMap, tail_length: &Option<f64>) -> Result<Vec<ChainNode>, (String, ErrorKind)> {
+    let mut effect_chain = parse_utils::parse_effects(effects)
+        .map_err(|message| (message, ErrorKind::InvalidValue))?;
+
+    let mut buses = BusUsage::default();
+    validate_chain(&mut effect_chain, effect_map, tail_length, &mut buses)?;
+    buses.finish().map_err(|message| (message, ErrorKind::InvalidValue))?;
+
+    Ok(effect_chain)
+}
+
+fn spinner(text: &str) -> ProgressBar {
+    let bar = ProgressBar::new_spinner();
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar.set_style(
+        ProgressStyle::with_template(format!("{text} {{msg}}{{spinner}}").as_str())
+        .unwrap()
+    );
+    bar
+}
+
+/// Envelope times count from the start of the signal, `offset` is where the buffer starts in it
+fn apply_effect(buffer: &mut AudioBuffer, effect_spec: &EffectSpec, effect_map: &EffectMap, tail_length: &Option<f64>, offset: usize) -> Result<(), (String, ErrorKind)> {
+    let effect = effect_map.get(&effect_spec.name).unwrap();
+    let bar = spinner(&format!("Applying effect '{}'", effect_spec.name));
+
+    let result = if effect_spec.envelopes.is_empty() {
+        effect.apply_effect(buffer, &effect_spec.arguments, tail_length)
+    } else {
+        let automation = automation::render_all(&effect_spec.envelopes, offset, buffer.channels[0].len(), buffer.spec.sample_rate);
+        effect.apply_automated(buffer, &effect_spec.arguments, &automation, tail_length)
+    };
+
+    let message = match result {
+        Ok(m) => m,
+        Err(message) => {
+            bar.finish_with_message(format!("{}", "failed".red()));
+            return Err((format!("{} -> {message}", effect_spec.name), ErrorKind::Io));
+        }
+    };
+
+    match audio_utils::sanitize_buffer(buffer) {
+        Ok(_) => {
+            if let Some(message) = message {
+                bar.finish_with_message(format!("... {} {}", "done".green(), format!("({})", message).yellow()));
+            } else {
+                bar.finish_with_message(format!("... {}", "done".green()));
+            }
+            Ok(())
+        },
+        Err(message) => {
+            bar.finish_with_message(format!("... {}","failed".red()));
+            Err((message, ErrorKind::ValueValidation))
+        }
+    }
+}
+
+fn apply_multiband(buffer: &mut AudioBuffer, effect_spec: &EffectSpec, branches: &[Vec<ChainNode>], effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<(), (String, ErrorKind)> {
+    let crossovers = multiband::crossovers(effect_spec, branches.len()).unwrap();
+    let bar = spinner(&format!("Splitting into {} bands", branches.len()));
+
+    let mut bands = match multiband::split(buffer, &crossovers) {
+        Ok(bands) => bands,
+        Err(message) => {
+            bar.finish_with_message(format!("... {}","failed".red()));
+            return Err((format!("{} -> {message}", multiband::NAME), ErrorKind::InvalidValue));
+        }
+    };
+    bar.finish_with_message(format!("... {}", "done".green()));
+
+    for (band, branch) in bands.iter_mut().zip(branches.iter()) {
+        run_chain(band, branch, effect_map, tail_length, buses, offset)?;
+    }
+
+    *buffer = multiband::sum(&bands).map_err(|message| (format!("{} -> {message}", multiband::NAME), ErrorKind::Io))?;
+    audio_utils::sanitize_buffer(buffer).map_err(|message| (message, ErrorKind::ValueValidation))
+}
+
+/// Runs every branch on a copy of the signal and sums the results
+fn apply_parallel(buffer: &mut AudioBuffer, branches: &[Vec<ChainNode>], effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<(), (String, ErrorKind)> {
+    let mut sum = AudioBuffer { spec: buffer.spec, channels: vec![Vec::new(); buffer.channels.len()] };
+
+    for branch in branches.iter() {
+        let mut copy = AudioBuffer { spec: buffer.spec, channels: buffer.channels.clone() };
+        run_chain(&mut copy, branch, effect_map, tail_length, buses, offset)?;
+        routing::mix_into(&mut sum, &copy, 1.0).map_err(|message| (message, ErrorKind::Io))?;
+    }
+
+    *buffer = sum;
+    audio_utils::sanitize_buffer(buffer).map_err(|message| (message, ErrorKind::ValueValidation))
+}
+
+/// Processes everything sent to the bus with its chain and adds it to the signal
+fn apply_return(buffer: &mut AudioBuffer, effect_spec: &EffectSpec, bus_chain: &[ChainNode], effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<(), (String, ErrorKind)> {
+    let bus = routing::bus_name(effect_spec).unwrap();
+    let mut signal = buses.remove(&bus).unwrap();
+
+    run_chain(&mut signal, bus_chain, effect_map, tail_length, buses, offset)?;
+    routing::mix_into(buffer, &signal, 1.0).map_err(|message| (format!("{} -> {message}", routing::RETURN_NAME), ErrorKind::Io))?;
+    audio_utils::sanitize_buffer(buffer).map_err(|message| (message, ErrorKind::ValueValidation))
+}
+
+/// Applies a single node and returns the latency it introduced
+fn apply_node(buffer: &mut AudioBuffer, node: &ChainNode, effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<usize, (String, ErrorKind)> {
+    match node {
+        ChainNode::Effect(effect_spec) if effect_spec.name == routing::SEND_NAME => {
+            let bus = routing::bus_name(effect_spec).unwrap();
+            let gain = routing::send_gain(effect_spec).unwrap();
+            routing::send(buses, bus, buffer, gain).map_err(|message| (format!("{} -> {message}", routing::SEND_NAME), ErrorKind::Io))?;
+            Ok(0)
+        },
+        ChainNode::Effect(effect_spec) if effect_spec.name == routing::RETURN_NAME => {
+            apply_return(buffer, effect_spec, &[], effect_map, tail_length, buses, offset)?;
+            Ok(0)
+        },
+        ChainNode::Effect(effect_spec) => {
+            apply_effect(buffer, effect_spec, effect_map, tail_length, offset)?;
+            Ok(effect_map.get(&effect_spec.name).unwrap().get_latency(&effect_spec.arguments, buffer.spec.sample_rate))
+        },
+        ChainNode::Group(effect_spec, branches) if effect_spec.name == routing::RETURN_NAME => {
+            apply_return(buffer, effect_spec, &branches[0], effect_map, tail_length, buses, offset)?;
+            Ok(0)
+        },
+        ChainNode::Group(effect_spec, branches) => {
+            apply_multiband(buffer, effect_spec, branches, effect_map, tail_length, buses, offset)?;
+            Ok(0)
+        },
+        ChainNode::Parallel(branches) => {
+            apply_parallel(buffer, branches, effect_map, tail_length, buses, offset)?;
+            Ok(0)
+        },
+    }
+}
+
+/// Applies a node to the part of the signal picked by its `@` modifier, if it has one
+fn apply_targeted(buffer: &mut AudioBuffer, node: &ChainNode, target: Option<&ChannelTarget>, effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<usize, (String, ErrorKind)> {
+    let Some(target) = target else {
+        return apply_node(buffer, node, effect_map, tail_length, buses, offset);
+    };
+    let in_context = |message: String| (format!("{} -> {message}", node_name(node)), ErrorKind::InvalidValue);
+
+    let mut part = channel_target::extract(buffer, target).map_err(in_context)?;
+    let latency = apply_node(&mut part, node, effect_map, tail_length, buses, offset)?;
+    channel_target::merge(buffer, part, target).map_err(in_context)?;
+    Ok(latency)
+}
+
+fn node_name(node: &ChainNode) -> &str {
+    match node {
+        ChainNode::Effect(effect_spec) | ChainNode::Group(effect_spec, _) => &effect_spec.name,
+        ChainNode::Parallel(_) => "parallel group",
+    }
+}
+
+fn changes_duration(node: &ChainNode, effect_map: &EffectMap) -> bool {
+    match node {
+        ChainNode::Effect(effect_spec) => effect_map.get(&effect_spec.name).is_some_and(|effect| effect.changes_duration()),
+        _ => false,
+    }
+}
+
+fn run_chain(buffer: &mut AudioBuffer, effect_chain: &[ChainNode], effect_map: &EffectMap, tail_length: &Option<f64>, buses: &mut Buses, offset: usize) -> Result<(), (String, ErrorKind)> {
+    for node in effect_chain.iter() {
+        let effect_spec = match node {
+            ChainNode::Effect(effect_spec) | ChainNode::Group(effect_spec, _) => Some(effect_spec),
+            ChainNode::Parallel(_) => None,
+        };
+        let mix = effect_spec.and_then(|spec| spec.mix);
+        let target = effect_spec.and_then(|spec| spec.target.as_ref());
+        let in_context = |message: String| (format!("{} -> {message}", node_name(node)), ErrorKind::InvalidValue);
+
+        let dry = mix.map(|_| AudioBuffer { spec: buffer.spec, channels: buffer.channels.clone() });
+
+        // The latency of a region is compensated when it is merged back
+        let latency = match effect_spec.and_then(|spec| spec.region.as_ref()) {
+            Some(region) if changes_duration(node, effect_map) => {
+                let range = region::range(buffer, region).map_err(in_context)?;
+                let mut part = region::copy(buffer, &range);
+                apply_targeted(&mut part, node, target, effect_map, tail_length, buses, offset + range.start)?;
+                region::splice(buffer, part, &range).map_err(in_context)?;
+                0
+            },
+            Some(region) => {
+                let range = region::range(buffer, region).map_err(in_context)?;
+                let (mut part, start, window) = region::extract(buffer, &range);
+                let latency = apply_targeted(&mut part, node, target, effect_map, tail_length, buses, offset + start)?;
+                region::merge(buffer, part, start, &window, latency).map_err(in_context)?;
+                0
+            },
+            None => apply_targeted(buffer, node, target, effect_map, tail_length, buses, offset)?,
+        };
+
+        if let (Some(mix), Some(dry)) = (mix, dry) {
+            mix_dry(buffer, &dry, mix, latency).map_err(in_context)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the chain in order. Sends, returns and parallel groups make it a small graph,
+/// which validation guarantees has no loops
+pub fn apply_chain(buffer: &mut AudioBuffer, effect_chain: &[ChainNode], effect_map: &EffectMap, tail_length: &Option<f64>) -> Result<(), (String, ErrorKind)> {
+    run_chain(buffer, effect_chain, effect_map, tail_length, &mut Buses::new(), 0)
+}
@@ -0,0 +1,65 @@
+use crate::types::{AudioBuffer, ChannelTarget};
+
+/// Copies the targeted part of the signal into its own buffer
+pub fn extract(buffer: &AudioBuffer, target: &ChannelTarget) -> Result<AudioBuffer, String> {
+    let channels = match target {
+        ChannelTarget::Channels(indices) => {
+            if let Some(index) = indices.iter().find(|i| **i >= buffer.channels.len()) {
+                return Err(format!("Channel {index} doesn't exist, the signal has {} channel(s)", buffer.channels.len()));
+            }
+            indices.iter().map(|i| buffer.channels[*i].clone()).collect()
+        },
+        ChannelTarget::Mid | ChannelTarget::Side => {
+            if buffer.channels.len() != 2 {
+                return Err(format!("Mid/side processing needs a stereo signal, the signal has {} channel(s)", buffer.channels.len()));
+            }
+            let sign = if *target == ChannelTarget::Mid { 1.0 } else { -1.0 };
+            let (left, right) = (&buffer.channels[0], &buffer.channels[1]);
+            vec![left.iter().zip(right.iter()).map(|(l, r)| (l + sign * r) / 2.0).collect()]
+        },
+    };
+
+    let mut spec = buffer.spec;
+    spec.channels = channels.len() as u16;
+    Ok(AudioBuffer { spec, channels })
+}
+
+/// Writes the processed part back. If it got longer, the rest of the signal is padded with silence.
+pub fn merge(buffer: &mut AudioBuffer, part: AudioBuffer, target: &ChannelTarget) -> Result<(), String> {
+    let expected = match target {
+        ChannelTarget::Channels(indices) => indices.len(),
+        ChannelTarget::Mid | ChannelTarget::Side => 1,
+    };
+    if part.channels.len() != expected {
+        return Err("Effects restricted with '@' can't change the amount of channels".to_string());
+    }
+
+    let length = part.channels[0].len().max(buffer.channels[0].len());
+    for channel in buffer.channels.iter_mut() {
+        channel.resize(length, 0.0);
+    }
+
+    match target {
+        ChannelTarget::Channels(indices) => {
+            for (index, mut channel) in indices.iter().zip(part.channels) {
+                channel.resize(length, 0.0);
+                buffer.channels[*index] = channel;
+            }
+        },
+        ChannelTarget::Mid | ChannelTarget::Side => {
+            // The untouched half is recovered from the original channels, then everything is decoded
+            let sign = if *target == ChannelTarget::Mid { 1.0 } else { -1.0 };
+            let processed = &part.channels[0];
+            let (left, right) = buffer.channels.split_at_mut(1);
+            for (n, (l, r)) in left[0].iter_mut().zip(right[0].iter_mut()).enumerate() {
+                let other = (*l - sign * *r) / 2.0;
+                let this = processed.get(n).copied().unwrap_or(0.0);
+                let (mid, side) = if sign > 0.0 { (this, other) } else { (other, this) };
+                *l = mid + side;
+                *r = mid - side;
+            }
+        },
+    }
+
+    Ok(())
+}
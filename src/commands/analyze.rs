@@ -0,0 +1,246 @@
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::{audio_utils, decoder, loudness, types::AudioBuffer};
+
+const CLIP_THRESHOLD: f64 = 0.999;
+const SILENCE_THRESHOLD_DB: f64 = -60.0;
+const SILENCE_WINDOW_SECONDS: f64 = 0.01;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(clap::Args)]
+pub struct AnalyzeArgs {
+    /// File path of the .wav file
+    file_path: PathBuf,
+
+    /// Output format of the report
+    #[arg(long, short, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+}
+
+struct SignalStats {
+    scope: String,
+    sample_peak: f64,
+    true_peak: f64,
+    rms: f64,
+    crest_factor: f64,
+    dc_offset: f64,
+    integrated: f64,
+    short_term_max: f64,
+    momentary_max: f64,
+    range: f64,
+    clipped_runs: usize,
+    clipped_samples: usize,
+    silence_percent: f64,
+}
+
+fn to_db(value: f64) -> f64 {
+    20.0 * value.log10()
+}
+
+/// Counts runs of consecutive samples at (or beyond) full scale, and the samples in those runs
+fn clipping(channel: &[f64]) -> (usize, usize) {
+    let mut runs = 0;
+    let mut samples = 0;
+    let mut in_run = false;
+
+    for sample in channel.iter() {
+        if sample.abs() >= CLIP_THRESHOLD {
+            samples += 1;
+            if !in_run {
+                runs += 1;
+                in_run = true;
+            }
+        } else {
+            in_run = false;
+        }
+    }
+
+    (runs, samples)
+}
+
+/// Share of short windows whose RMS is below the silence threshold
+fn silence_percent(channels: &[Vec<f64>], sample_rate: u32) -> f64 {
+    let window = ((SILENCE_WINDOW_SECONDS * sample_rate as f64) as usize).max(1);
+    let threshold = 10.0_f64.powf(SILENCE_THRESHOLD_DB / 20.0);
+    let length = channels[0].len();
+
+    let mut silent = 0;
+    let mut total = 0;
+    for start in (0..length).step_by(window) {
+        let end = (start + window).min(length);
+        let count = (end - start) * channels.len();
+        let square_sum: f64 = channels.iter().flat_map(|c| c[start..end].iter()).map(|s| s * s).sum();
+        if (square_sum / count as f64).sqrt() < threshold {
+            silent += 1;
+        }
+        total += 1;
+    }
+
+    100.0 * silent as f64 / total as f64
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let product: f64 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let energy_a: f64 = a.iter().map(|x| x * x).sum();
+    let energy_b: f64 = b.iter().map(|y| y * y).sum();
+
+    if energy_a == 0.0 || energy_b == 0.0 {
+        return 0.0;
+    }
+
+    product / (energy_a * energy_b).sqrt()
+}
+
+fn signal_stats(scope: String, buffer: &AudioBuffer) -> SignalStats {
+    let sample_count: usize = buffer.channels.iter().map(|c| c.len()).sum();
+    let samples = || buffer.channels.iter().flat_map(|c| c.iter());
+
+    let sample_peak = samples().fold(0.0, |max: f64, s| max.max(s.abs()));
+    let rms = (samples().map(|s| s * s).sum::<f64>() / sample_count as f64).sqrt();
+    let dc_offset = samples().sum::<f64>() / sample_count as f64;
+    let (clipped_runs, clipped_samples) = buffer.channels.iter()
+        .map(|c| clipping(c))
+        .fold((0, 0), |(runs, total), (r, s)| (runs + r, total + s));
+    let loudness = loudness::measure(buffer);
+
+    SignalStats {
+        scope,
+        sample_peak: to_db(sample_peak),
+        true_peak: to_db(loudness.true_peak),
+        rms: to_db(rms),
+        crest_factor: to_db(sample_peak) - to_db(rms),
+        dc_offset,
+        integrated: loudness.integrated,
+        short_term_max: loudness.short_term_max,
+        momentary_max: loudness.momentary_max,
+        range: loudness.range,
+        clipped_runs,
+        clipped_samples,
+        silence_percent: silence_percent(&buffer.channels, buffer.spec.sample_rate),
+    }
+}
+
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:.4}")
+    } else {
+        "null".to_string()
+    }
+}
+
+/// A JSON string literal, with quotes, backslashes and control characters escaped
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if character.is_control() => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn print_text(path: &Path, buffer: &AudioBuffer, reports: &[SignalStats], stereo_correlation: Option<f64>) {
+    println!("{}", format!("Analysis of {path:#?}").bold());
+    println!("   Sample rate: {}, Duration: {}s, Channels: {}\n",
+        buffer.spec.sample_rate.to_string().bright_blue(),
+        format!("{:.2}", audio_utils::get_buffer_duration(buffer)).bright_blue(),
+        buffer.spec.channels.to_string().bright_blue()
+    );
+
+    for report in reports.iter() {
+        println!("{}", report.scope.bold());
+        println!("   Sample peak:           {}", format!("{:.2} dBFS", report.sample_peak).bright_blue());
+        println!("   True peak:             {}", format!("{:.2} dBTP", report.true_peak).bright_blue());
+        println!("   RMS:                   {}", format!("{:.2} dBFS", report.rms).bright_blue());
+        println!("   Crest factor:          {}", format!("{:.2} dB", report.crest_factor).bright_blue());
+        println!("   DC offset:             {}", format!("{:.6}", report.dc_offset).bright_blue());
+        println!("   Integrated loudness:   {}", format!("{:.1} LUFS", report.integrated).bright_blue());
+        println!("   Max short-term:        {}", format!("{:.1} LUFS", report.short_term_max).bright_blue());
+        println!("   Max momentary:         {}", format!("{:.1} LUFS", report.momentary_max).bright_blue());
+        println!("   Loudness range:        {}", format!("{:.1} LU", report.range).bright_blue());
+        println!("   Clipped runs:          {}", format!("{} ({} samples)", report.clipped_runs, report.clipped_samples).bright_blue());
+        println!("   Silence:               {}", format!("{:.1}%", report.silence_percent).bright_blue());
+    }
+
+    if let Some(value) = stereo_correlation {
+        println!("\n   Stereo correlation:    {}", format!("{value:.3}").bright_blue());
+    }
+}
+
+fn print_json(path: &Path, buffer: &AudioBuffer, reports: &[SignalStats], stereo_correlation: Option<f64>) {
+    let scopes: Vec<String> = reports.iter().map(|r| format!(
+        "    {{\"scope\": {}, \"sample_peak_db\": {}, \"true_peak_db\": {}, \"rms_db\": {}, \"crest_factor_db\": {}, \"dc_offset\": {}, \
+\"integrated_lufs\": {}, \"short_term_max_lufs\": {}, \"momentary_max_lufs\": {}, \"lra_lu\": {}, \"clipped_runs\": {}, \"clipped_samples\": {}, \"silence_percent\": {}}}",
+        json_string(&r.scope), json_number(r.sample_peak), json_number(r.true_peak), json_number(r.rms), json_number(r.crest_factor),
+        json_number(r.dc_offset), json_number(r.integrated), json_number(r.short_term_max), json_number(r.momentary_max),
+        json_number(r.range), r.clipped_runs, r.clipped_samples, json_number(r.silence_percent)
+    )).collect();
+
+    println!("{{");
+    println!("  \"file\": {},", json_string(&path.to_string_lossy()));
+    println!("  \"sample_rate\": {},", buffer.spec.sample_rate);
+    println!("  \"duration\": {},", json_number(audio_utils::get_buffer_duration(buffer)));
+    println!("  \"channels\": {},", buffer.spec.channels);
+    println!("  \"stereo_correlation\": {},", stereo_correlation.map_or("null".to_string(), json_number));
+    println!("  \"scopes\": [\n{}\n  ]", scopes.join(",\n"));
+    println!("}}");
+}
+
+fn print_csv(reports: &[SignalStats], stereo_correlation: Option<f64>) {
+    println!("scope,sample_peak_db,true_peak_db,rms_db,crest_factor_db,dc_offset,integrated_lufs,short_term_max_lufs,momentary_max_lufs,lra_lu,clipped_runs,clipped_samples,silence_percent,stereo_correlation");
+    for r in reports.iter() {
+        println!("{},{:.4},{:.4},{:.4},{:.4},{:.6},{:.4},{:.4},{:.4},{:.4},{},{},{:.4},{}",
+            r.scope, r.sample_peak, r.true_peak, r.rms, r.crest_factor, r.dc_offset, r.integrated,
+            r.short_term_max, r.momentary_max, r.range, r.clipped_runs, r.clipped_samples, r.silence_percent,
+            stereo_correlation.map_or(String::new(), |c| format!("{c:.4}"))
+        );
+    }
+}
+
+pub fn run(args: AnalyzeArgs) -> Result<(), String> {
+    let buffer = decoder::read_file(&args.file_path)?;
+    if buffer.channels.is_empty() || buffer.channels[0].is_empty() {
+        return Err("Audio file contains no samples".to_string());
+    }
+
+    let mut reports = Vec::new();
+    if buffer.channels.len() > 1 {
+        for (index, channel) in buffer.channels.iter().enumerate() {
+            let mut spec = buffer.spec;
+            spec.channels = 1;
+            let single = AudioBuffer { spec, channels: vec![channel.clone()] };
+            reports.push(signal_stats(format!("Channel {index}"), &single));
+        }
+    }
+    reports.push(signal_stats("Overall".to_string(), &buffer));
+
+    let stereo_correlation = if buffer.channels.len() >= 2 {
+        Some(correlation(&buffer.channels[0], &buffer.channels[1]))
+    } else {
+        None
+    };
+
+    match args.format {
+        ReportFormat::Text => print_text(&args.file_path, &buffer, &reports, stereo_correlation),
+        ReportFormat::Json => print_json(&args.file_path, &buffer, &reports, stereo_correlation),
+        ReportFormat::Csv => print_csv(&reports, stereo_correlation),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use colored::Colorize;
+
+use crate::{audio_utils::{self, FadeCurve}, decoder, encoder};
+
+#[derive(clap::Args)]
+pub struct ConcatArgs {
+    /// The .wav files to join, in order
+    #[arg(required = true, num_args = 2..)]
+    files: Vec<PathBuf>,
+
+    /// Where to write the joined file
+    #[arg(long, short, required = true)]
+    output: PathBuf,
+
+    /// Length of the crossfade between consecutive files, in seconds
+    #[arg(long, short, default_value_t = 0.0)]
+    crossfade: f64,
+
+    /// Shape of the crossfade
+    #[arg(long, value_enum, default_value_t = FadeCurve::EqualPower)]
+    curve: FadeCurve,
+}
+
+pub fn run(args: ConcatArgs) -> Result<(), String> {
+    if args.crossfade < 0.0 {
+        return Err("Crossfade can't be negative".to_string());
+    }
+
+    let mut joined = decoder::read_file(&args.files[0])?;
+    let overlap = (args.crossfade * joined.spec.sample_rate as f64).round() as usize;
+    println!("{}", format!("Joining {} files", args.files.len()).bold());
+
+    for path in args.files[1..].iter() {
+        let next = decoder::read_file(path)?;
+        if next.spec.sample_rate != joined.spec.sample_rate {
+            return Err(format!("{:#?} has a sample rate of {}, expected {}", path, next.spec.sample_rate, joined.spec.sample_rate));
+        }
+        audio_utils::join(&mut joined, &next, overlap, args.curve).map_err(|message| format!("{:#?}: {message}", path))?;
+    }
+
+    audio_utils::sanitize_buffer(&mut joined)?;
+    let clipped = encoder::encode_file(&joined, args.output.clone()).map_err(|e| e.to_string())?;
+
+    println!("   Output duration:       {}", format!("{:.2}s", audio_utils::get_buffer_duration(&joined)).bright_blue());
+    println!("   Written to {:#?}", args.output);
+    if clipped > 0 {
+        println!("   Clipping: {} samples", clipped.to_string().yellow());
+    }
+
+    Ok(())
+}
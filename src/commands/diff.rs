@@ -0,0 +1,186 @@
+use std::path::PathBuf;
+use colored::Colorize;
+use rustfft::{FftPlanner, num_complex::Complex};
+
+use crate::{decoder, encoder, spectrum, types::AudioBuffer};
+
+const SPECTRUM_FFT_SIZE: usize = 4096;
+const OCTAVE_BAND_CENTERS: [f64; 10] = [31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+
+#[derive(clap::Args)]
+pub struct DiffArgs {
+    /// The reference .wav file
+    file_a: PathBuf,
+
+    /// The .wav file compared against the reference
+    file_b: PathBuf,
+
+    /// Align the files by cross-correlation before subtracting
+    #[arg(long)]
+    align: bool,
+
+    /// Maximum offset in seconds searched when aligning
+    #[arg(long, default_value_t = 1.0)]
+    max_offset: f64,
+
+    /// Samples differing by less than this level (dBFS) are considered equal
+    #[arg(long, default_value_t = -120.0, allow_negative_numbers = true)]
+    threshold: f64,
+
+    /// Where to write the difference signal (.wav)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn to_db(value: f64) -> f64 {
+    20.0 * value.log10()
+}
+
+fn mono_sum(buffer: &AudioBuffer) -> Vec<f64> {
+    let mut sum = vec![0.0; buffer.channels[0].len()];
+    for channel in buffer.channels.iter() {
+        for (total, sample) in sum.iter_mut().zip(channel.iter()) {
+            *total += sample;
+        }
+    }
+    sum
+}
+
+/// Lag (in samples) of `b` relative to `a` with the highest cross-correlation
+fn find_lag(a: &[f64], b: &[f64], max_lag: usize) -> isize {
+    let size = (a.len() + b.len()).next_power_of_two();
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let to_complex = |signal: &[f64]| {
+        let mut data: Vec<Complex<f64>> = signal.iter().map(|s| Complex::new(*s, 0.0)).collect();
+        data.resize(size, Complex::new(0.0, 0.0));
+        data
+    };
+
+    let mut spectrum_a = to_complex(a);
+    let mut spectrum_b = to_complex(b);
+    forward.process(&mut spectrum_a);
+    forward.process(&mut spectrum_b);
+
+    let mut correlation: Vec<Complex<f64>> = spectrum_a.iter().zip(spectrum_b.iter()).map(|(x, y)| x.conj() * y).collect();
+    inverse.process(&mut correlation);
+
+    // Index k holds lag k, index size - k holds lag -k
+    let mut best = (0, f64::MIN);
+    for lag in -(max_lag as isize)..=max_lag as isize {
+        let index = lag.rem_euclid(size as isize) as usize;
+        if correlation[index].re > best.1 {
+            best = (lag, correlation[index].re);
+        }
+    }
+    best.0
+}
+
+fn band_powers(buffer: &AudioBuffer) -> Vec<f64> {
+    let fs = buffer.spec.sample_rate as f64;
+    let mut bands = vec![0.0; OCTAVE_BAND_CENTERS.len()];
+
+    for channel in buffer.channels.iter() {
+        for frame in spectrum::stft(channel, SPECTRUM_FFT_SIZE, SPECTRUM_FFT_SIZE / 2) {
+            for (bin, magnitude) in frame.iter().enumerate() {
+                let freq = bin as f64 * fs / SPECTRUM_FFT_SIZE as f64;
+                let band = OCTAVE_BAND_CENTERS.iter()
+                    .position(|center| freq >= center / 2.0_f64.sqrt() && freq < center * 2.0_f64.sqrt());
+                if let Some(band) = band {
+                    bands[band] += magnitude * magnitude;
+                }
+            }
+        }
+    }
+
+    bands
+}
+
+pub fn run(args: DiffArgs) -> Result<(), String> {
+    let a = decoder::read_file(&args.file_a)?;
+    let b = decoder::read_file(&args.file_b)?;
+
+    if a.spec.sample_rate != b.spec.sample_rate {
+        return Err(format!("Sample rates differ ({} vs {})", a.spec.sample_rate, b.spec.sample_rate));
+    }
+    if a.channels.len() != b.channels.len() {
+        return Err(format!("Channel counts differ ({} vs {})", a.channels.len(), b.channels.len()));
+    }
+    if a.channels[0].is_empty() || b.channels[0].is_empty() {
+        return Err("Audio file contains no samples".to_string());
+    }
+
+    let lag = if args.align {
+        let max_lag = (args.max_offset.max(0.0) * a.spec.sample_rate as f64) as usize;
+        find_lag(&mono_sum(&a), &mono_sum(&b), max_lag)
+    } else {
+        0
+    };
+
+    // The difference covers the longer of both files, missing samples count as silence
+    let length = a.channels[0].len().max((b.channels[0].len() as isize - lag).max(0) as usize);
+    let threshold = 10.0_f64.powf(args.threshold / 20.0);
+    let aligned_b = AudioBuffer {
+        spec: b.spec,
+        channels: b.channels.iter().map(|channel| (0..length).map(|n| {
+            let position = n as isize + lag;
+            if position >= 0 { channel.get(position as usize).copied().unwrap_or(0.0) } else { 0.0 }
+        }).collect()).collect(),
+    };
+
+    let mut first_difference: Option<(usize, usize)> = None;
+    let mut residual = AudioBuffer { spec: a.spec, channels: Vec::with_capacity(a.channels.len()) };
+
+    for (index, (channel_a, channel_b)) in a.channels.iter().zip(aligned_b.channels.iter()).enumerate() {
+        let difference: Vec<f64> = (0..length)
+            .map(|n| channel_a.get(n).copied().unwrap_or(0.0) - channel_b[n])
+            .collect();
+
+        let first = difference.iter().position(|d| d.abs() > threshold);
+        if let Some(n) = first && first_difference.is_none_or(|(earliest, _)| n < earliest) {
+            first_difference = Some((n, index));
+        }
+        residual.channels.push(difference);
+    }
+
+    let sample_count = (length * residual.channels.len()) as f64;
+    let peak = residual.channels.iter().flatten().fold(0.0, |max: f64, s| max.max(s.abs()));
+    let rms = (residual.channels.iter().flatten().map(|s| s * s).sum::<f64>() / sample_count).sqrt();
+    let reference_rms = (a.channels.iter().flatten().map(|s| s * s).sum::<f64>() / sample_count).sqrt();
+
+    println!("{}", format!("Difference of {:#?} and {:#?}", args.file_a, args.file_b).bold());
+    if args.align {
+        println!("   Alignment offset:      {}", format!("{lag} samples ({:.2} ms)", lag as f64 * 1000.0 / a.spec.sample_rate as f64).bright_blue());
+    }
+    println!("   Residual peak:         {}", format!("{:.2} dBFS", to_db(peak)).bright_blue());
+    println!("   Residual RMS:          {}", format!("{:.2} dBFS", to_db(rms)).bright_blue());
+    println!("   Null depth:            {}", format!("{:.2} dB", to_db(rms) - to_db(reference_rms)).bright_blue());
+    match first_difference {
+        Some((n, channel)) => println!("   First difference:      {}", format!("sample {n} ({:.4}s), channel {channel}", n as f64 / a.spec.sample_rate as f64).bright_blue()),
+        None => println!("   First difference:      {}", "none, the files null".green()),
+    }
+
+    let powers_a = band_powers(&a);
+    let powers_b = band_powers(&aligned_b);
+    println!("\n   Octave band level of B relative to A:");
+    for ((center, power_a), power_b) in OCTAVE_BAND_CENTERS.iter().zip(powers_a.iter()).zip(powers_b.iter()) {
+        if *center >= a.spec.sample_rate as f64 / 2.0 {
+            break;
+        }
+        let difference = 10.0 * (power_b / power_a).log10();
+        let text = if difference.is_finite() { format!("{difference:+.2} dB") } else { "n/a".to_string() };
+        println!("   {:>8} Hz:           {}", center, text.bright_blue());
+    }
+
+    if let Some(path) = args.output {
+        let clipped = encoder::encode_file(&residual, path.clone()).map_err(|e| e.to_string())?;
+        println!("\n   Difference written to {:#?}", path);
+        if clipped > 0 {
+            println!("   Clipping: {} samples", clipped.to_string().yellow());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod analyze;
+pub mod concat;
+pub mod diff;
+pub mod render;
+pub mod response;
+pub mod split;
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use colored::Colorize;
+
+use crate::{chain::{self, EffectMap}, decoder, plot::{self, Canvas}, region, types::AudioBuffer};
+
+#[derive(clap::Args)]
+pub struct RenderArgs {
+    /// File path of the .wav file
+    file_path: PathBuf,
+
+    /// Optional effects chain, the input and output are rendered side by side
+    effects: Vec<String>,
+
+    /// Where to write the waveform image (.png)
+    #[arg(long)]
+    waveform: Option<PathBuf>,
+
+    /// Where to write the spectrogram image (.png)
+    #[arg(long)]
+    spectrogram: Option<PathBuf>,
+
+    /// FFT size of the spectrogram in samples
+    #[arg(long, default_value_t = 2048)]
+    fft_size: usize,
+
+    /// Hop size between spectrogram frames in samples
+    #[arg(long, default_value_t = 512)]
+    hop: usize,
+
+    /// Width of each rendered buffer in pixels
+    #[arg(long, default_value_t = 1200)]
+    width: u32,
+
+    /// Height of the images in pixels
+    #[arg(long, default_value_t = 600)]
+    height: u32,
+
+    /// Set a fixed tail duration in seconds
+    #[arg(long, short)]
+    tail: Option<f64>,
+}
+
+fn render_pair<F: Fn(&AudioBuffer) -> Canvas>(input: &AudioBuffer, output: &Option<AudioBuffer>, render: F) -> Canvas {
+    match output {
+        Some(output) => Canvas::side_by_side(&render(input), &render(output)),
+        None => render(input),
+    }
+}
+
+pub fn run(args: RenderArgs, effect_map: &EffectMap) -> Result<(), String> {
+    if args.waveform.is_none() && args.spectrogram.is_none() {
+        return Err("Nothing to render (use --waveform and/or --spectrogram)".to_string());
+    }
+
+    if args.fft_size < 16 || args.hop == 0 {
+        return Err("FFT size must be at least 16 and hop must be at least 1".to_string());
+    }
+
+    plot::verify_dimensions(args.width, args.height)?;
+
+    let mut effect_chain = chain::prepare_chain(&args.effects, effect_map, &args.tail).map_err(|(message, _)| message)?;
+
+    eprintln!("{}", format!("Reading file {:#?}", &args.file_path).bold());
+    let input = decoder::read_file(&args.file_path)?;
+    if input.channels.is_empty() || input.channels[0].is_empty() {
+        return Err("Audio file contains no samples".to_string());
+    }
+    region::resolve_cues(&mut effect_chain, &decoder::read_cues(&args.file_path)?)?;
+
+    let output = if effect_chain.is_empty() {
+        None
+    } else {
+        let mut output = AudioBuffer { spec: input.spec, channels: input.channels.clone() };
+        chain::apply_chain(&mut output, &effect_chain, effect_map, &args.tail).map_err(|(message, _)| message)?;
+        Some(output)
+    };
+
+    if let Some(path) = args.waveform {
+        render_pair(&input, &output, |buffer| plot::render_waveform(buffer, args.width, args.height)).save(&path)?;
+        eprintln!("   Waveform written to {:#?}", path);
+    }
+
+    if let Some(path) = args.spectrogram {
+        render_pair(&input, &output, |buffer| plot::render_spectrogram(buffer, args.fft_size, args.hop, args.width, args.height)).save(&path)?;
+        eprintln!("   Spectrogram written to {:#?}", path);
+    }
+
+    Ok(())
+}
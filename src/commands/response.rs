@@ -0,0 +1,155 @@
+use core::f64;
+use std::{fs, path::PathBuf};
+use hound::{SampleFormat, WavSpec};
+use rustfft::num_complex::Complex;
+
+use crate::{chain::{self, EffectMap}, plot, types::{AudioBuffer, BiquadCoefficients, ChainNode}};
+
+const MIN_FREQ: f64 = 20.0;
+
+#[derive(clap::Args)]
+pub struct ResponseArgs {
+    /// The effects chain
+    #[arg(required = true)]
+    effects: Vec<String>,
+
+    /// Sample rate the chain is evaluated at
+    #[arg(long, short = 'r', default_value_t = 48000)]
+    sample_rate: u32,
+
+    /// Amount of (logarithmically spaced) frequency points
+    #[arg(long, default_value_t = 512)]
+    points: usize,
+
+    /// Length of the impulse response in samples, used when the chain can't be evaluated analytically
+    #[arg(long, default_value_t = 65536)]
+    impulse_length: usize,
+
+    /// Where to write the CSV (defaults to stdout)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Where to write a magnitude and phase plot (.png)
+    #[arg(long)]
+    plot: Option<PathBuf>,
+
+    /// Width of the plot in pixels
+    #[arg(long, default_value_t = 1200)]
+    width: u32,
+
+    /// Height of the plot in pixels
+    #[arg(long, default_value_t = 600)]
+    height: u32,
+
+    /// Set a fixed tail duration in seconds
+    #[arg(long, short)]
+    tail: Option<f64>,
+}
+
+fn biquad_response(coefficients: &BiquadCoefficients, w: f64) -> Complex<f64> {
+    let z1 = Complex::from_polar(1.0, -w);
+    let z2 = z1 * z1;
+    let numerator = coefficients.b0 + z1 * coefficients.b1 + z2 * coefficients.b2;
+    let denominator = coefficients.a0 + z1 * coefficients.a1 + z2 * coefficients.a2;
+    numerator / denominator
+}
+
+/// Biquad sections of the whole chain, if every effect in it exposes them and none is mixed or restricted with a modifier or region, nor automated
+fn chain_biquads(effect_chain: &[ChainNode], effect_map: &EffectMap, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+    let mut sections = Vec::new();
+    for node in effect_chain.iter() {
+        let ChainNode::Effect(effect_spec) = node else {
+            return None;
+        };
+        if effect_spec.mix.is_some() || effect_spec.target.is_some() || effect_spec.region.is_some() || !effect_spec.envelopes.is_empty() {
+            return None;
+        }
+        let effect = effect_map.get(&effect_spec.name)?;
+        sections.extend(effect.get_biquads(&effect_spec.arguments, sample_rate)?);
+    }
+    Some(sections)
+}
+
+fn impulse_response(args: &ResponseArgs, effect_chain: &[ChainNode], effect_map: &EffectMap) -> Result<Vec<f64>, String> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: args.sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let mut impulse = vec![0.0; args.impulse_length];
+    impulse[0] = 1.0;
+
+    let mut buffer = AudioBuffer { spec, channels: vec![impulse] };
+    chain::apply_chain(&mut buffer, effect_chain, effect_map, &args.tail).map_err(|(message, _)| message)?;
+    Ok(buffer.channels.swap_remove(0))
+}
+
+/// Evaluates the DTFT of `response` at angular frequency `w`
+fn dtft(response: &[f64], w: f64) -> Complex<f64> {
+    let step = Complex::from_polar(1.0, -w);
+    let mut phasor = Complex::new(1.0, 0.0);
+    let mut sum = Complex::new(0.0, 0.0);
+    for sample in response.iter() {
+        sum += phasor * sample;
+        phasor *= step;
+    }
+    sum
+}
+
+pub fn run(args: ResponseArgs, effect_map: &EffectMap) -> Result<(), String> {
+    if args.points < 2 || args.impulse_length == 0 {
+        return Err("At least 2 points and an impulse length of 1 sample are required".to_string());
+    }
+
+    let effect_chain = chain::prepare_chain(&args.effects, effect_map, &args.tail).map_err(|(message, _)| message)?;
+
+    let fs = args.sample_rate as f64;
+    let max_freq = fs / 2.0;
+    if max_freq <= MIN_FREQ {
+        return Err(format!("Sample rate must be above {} Hz", 2.0 * MIN_FREQ));
+    }
+
+    let frequencies: Vec<f64> = (0..args.points)
+        .map(|i| MIN_FREQ * (max_freq / MIN_FREQ).powf(i as f64 / (args.points - 1) as f64))
+        .collect();
+
+    let responses: Vec<Complex<f64>> = match chain_biquads(&effect_chain, effect_map, args.sample_rate) {
+        Some(sections) => {
+            eprintln!("   Computing the response analytically from {} biquad section(s)", sections.len());
+            frequencies.iter().map(|f| {
+                let w = 2.0 * f64::consts::PI * f / fs;
+                sections.iter().map(|s| biquad_response(s, w)).product()
+            }).collect()
+        },
+        None => {
+            eprintln!("   Computing the response from an impulse of {} samples", args.impulse_length);
+            let response = impulse_response(&args, &effect_chain, effect_map)?;
+            frequencies.iter().map(|f| dtft(&response, 2.0 * f64::consts::PI * f / fs)).collect()
+        }
+    };
+
+    let magnitudes: Vec<f64> = responses.iter().map(|h| 20.0 * h.norm().log10()).collect();
+    let phases: Vec<f64> = responses.iter().map(|h| h.arg().to_degrees()).collect();
+
+    let mut csv = String::from("freq_hz,magnitude_db,phase_deg\n");
+    for ((freq, magnitude), phase) in frequencies.iter().zip(magnitudes.iter()).zip(phases.iter()) {
+        csv.push_str(&format!("{freq:.3},{magnitude:.4},{phase:.4}\n"));
+    }
+
+    match &args.output {
+        Some(path) => {
+            fs::write(path, csv).map_err(|e| e.to_string())?;
+            eprintln!("   Response written to {:#?}", path);
+        },
+        None => print!("{csv}"),
+    }
+
+    if let Some(path) = &args.plot {
+        plot::verify_dimensions(args.width, args.height)?;
+        plot::render_response(&frequencies, &magnitudes, &phases, args.width, args.height).save(path)?;
+        eprintln!("   Plot written to {:#?}", path);
+    }
+
+    Ok(())
+}
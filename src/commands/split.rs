@@ -0,0 +1,84 @@
+use std::{ops::Range, path::{Path, PathBuf}};
+use clap::ArgGroup;
+use colored::Colorize;
+
+use crate::{audio_utils::{self, SILENCE_RMS}, decoder, encoder, types::AudioBuffer};
+
+#[derive(clap::Args)]
+#[group(skip)]
+#[command(group = ArgGroup::new("mode").required(true).args(["on_silence", "every"]))]
+pub struct SplitArgs {
+    /// The .wav file to split
+    file_path: PathBuf,
+
+    /// Write every region between silences to its own file
+    #[arg(long)]
+    on_silence: bool,
+
+    /// Split into pieces of this many seconds
+    #[arg(long)]
+    every: Option<f64>,
+
+    /// Level (dBFS RMS) under which audio counts as silence
+    #[arg(long, default_value_t = 20.0 * SILENCE_RMS.log10(), allow_negative_numbers = true)]
+    threshold: f64,
+
+    /// Shortest silence (in seconds) that separates two regions
+    #[arg(long, default_value_t = 0.5)]
+    min_silence: f64,
+
+    /// Output path, the files get numbered (take.wav becomes take_001.wav, take_002.wav, ...).
+    /// Defaults to the input path
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn numbered_path(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{stem}_{number:03}.wav"))
+}
+
+pub fn run(args: SplitArgs) -> Result<(), String> {
+    let buffer = decoder::read_file(&args.file_path)?;
+    let sample_rate = buffer.spec.sample_rate as f64;
+    let length = buffer.channels[0].len();
+    if length == 0 {
+        return Err("Audio file contains no samples".to_string());
+    }
+
+    let regions: Vec<Range<usize>> = match args.every {
+        Some(seconds) => {
+            let piece = (seconds * sample_rate).round() as usize;
+            if piece == 0 {
+                return Err("Pieces must be at least one sample long".to_string());
+            }
+            (0..length).step_by(piece).map(|start| start..(start + piece).min(length)).collect()
+        },
+        None => {
+            if args.min_silence < 0.0 {
+                return Err("Minimum silence can't be negative".to_string());
+            }
+            let threshold = 10.0_f64.powf(args.threshold / 20.0);
+            audio_utils::find_sound(&buffer, threshold, (args.min_silence * sample_rate).round() as usize)
+        },
+    };
+
+    if regions.is_empty() {
+        return Err("No sound found above the threshold".to_string());
+    }
+
+    let output = args.output.unwrap_or(args.file_path.clone());
+    println!("{}", format!("Splitting {:#?} into {} file(s)", args.file_path, regions.len()).bold());
+
+    for (index, region) in regions.iter().enumerate() {
+        let piece = AudioBuffer {
+            spec: buffer.spec,
+            channels: buffer.channels.iter().map(|channel| channel[region.clone()].to_vec()).collect(),
+        };
+        let path = numbered_path(&output, index + 1);
+        encoder::encode_file(&piece, path.clone()).map_err(|e| e.to_string())?;
+        println!("   {:#?}: {}", path, format!("{:.3}s - {:.3}s", region.start as f64 / sample_rate, region.end as f64 / sample_rate).bright_blue());
+    }
+
+    Ok(())
+}
@@ -1,10 +1,10 @@
-use crate::types::{AudioBuffer, I24_MAX};
-use std::{path::PathBuf};
+use crate::types::{AudioBuffer, Cues, I24_MAX};
+use std::{collections::HashMap, fs::File, io::{Read, Seek, SeekFrom}, path::PathBuf};
 use hound::WavReader;
 use hound::SampleFormat::{Float,Int};
 
 pub fn read_file(path:&PathBuf) -> Result<AudioBuffer, String> {
-    let mut reader = match WavReader::open(&path) {
+    let mut reader = match WavReader::open(path) {
         Ok(val) => val,
         Err(val) => {return Err(val.to_string());}
     };
@@ -42,8 +42,67 @@ pub fn read_file(path:&PathBuf) -> Result<AudioBuffer, String> {
         result[channel].push(*sample);
     }
 
-    return Ok(AudioBuffer {
+    Ok(AudioBuffer {
         spec,
         channels: result
-    });    
-}
\ No newline at end of file
+    })
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads the cue points (markers) of a .wav file from its `cue ` chunk, named after the `labl` entries of
+/// its `LIST`/`adtl` chunk. Cue points without a label are called `cue<id>`.
+pub fn read_cues(path: &PathBuf) -> Result<Cues, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header).map_err(|e| e.to_string())?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("Not a RIFF .wav file".to_string());
+    }
+
+    let mut positions: Vec<(u32, usize)> = Vec::new();
+    let mut labels: HashMap<u32, String> = HashMap::new();
+    let mut chunk_header = [0u8; 8];
+
+    // Chunks are padded to an even size, a truncated chunk ends the search like it ends the file
+    while file.read_exact(&mut chunk_header).is_ok() {
+        let size = le_u32(&chunk_header, 4).unwrap() as usize;
+        let id = &chunk_header[0..4];
+        if id != b"cue " && id != b"LIST" {
+            if file.seek(SeekFrom::Current((size + size % 2) as i64)).is_err() {
+                break;
+            }
+            continue;
+        }
+
+        let mut data = vec![0u8; size + size % 2];
+        if file.read_exact(&mut data).is_err() {
+            break;
+        }
+
+        if id == b"cue " {
+            // Cue points are 24 bytes: id, play order, chunk id, chunk start, block start, sample offset
+            let count = le_u32(&data, 0).unwrap_or(0) as usize;
+            for point in data[4.min(size)..].chunks_exact(24).take(count) {
+                positions.push((le_u32(point, 0).unwrap(), le_u32(point, 20).unwrap() as usize));
+            }
+        } else if data.starts_with(b"adtl") {
+            let mut offset = 4;
+            while let Some(sub_size) = le_u32(&data, offset + 4).map(|s| s as usize) {
+                let sub_data = data.get(offset + 8..(offset + 8 + sub_size).min(size)).unwrap_or(&[]);
+                if &data[offset..offset + 4] == b"labl" && let Some(cue_id) = le_u32(sub_data, 0) {
+                    let text = String::from_utf8_lossy(&sub_data[4..]);
+                    labels.insert(cue_id, text.trim_end_matches('\0').trim().to_lowercase());
+                }
+                offset += 8 + sub_size + sub_size % 2;
+            }
+        }
+    }
+
+    Ok(positions.into_iter().map(|(id, position)| {
+        let name = labels.remove(&id).filter(|label| !label.is_empty()).unwrap_or(format!("cue{id}"));
+        (name, position)
+    }).collect())
+}
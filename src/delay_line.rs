@@ -0,0 +1,37 @@
+/// Delay line that can be read between samples, for delays that change over time
+pub struct DelayLine {
+    line: Vec<f64>,
+    write: usize,
+}
+
+impl DelayLine {
+    /// Holds enough samples for delays up to `max_delay`
+    pub fn new(max_delay: usize) -> DelayLine {
+        DelayLine { line: vec![0.0; max_delay + 4], write: 0 }
+    }
+
+    pub fn push(&mut self, sample: f64) {
+        self.line[self.write] = sample;
+        self.write = (self.write + 1) % self.line.len();
+    }
+
+    /// Sample pushed `age` pushes before the last one
+    fn get(&self, age: usize) -> f64 {
+        let length = self.line.len();
+        self.line[(self.write + 2 * length - 1 - age.min(length - 1)) % length]
+    }
+
+    /// Signal `delay` samples before the last pushed sample, with cubic Hermite interpolation in between samples.
+    /// A delay of 1 is the sample pushed before the last one.
+    pub fn read(&self, delay: f64) -> f64 {
+        let delay = delay.max(0.0);
+        let index = delay.floor() as usize;
+        let fraction = delay - index as f64;
+
+        let (newer, x0, x1, older) = (self.get(index.saturating_sub(1)), self.get(index), self.get(index + 1), self.get(index + 2));
+        let c1 = 0.5 * (x1 - newer);
+        let c2 = newer - 2.5 * x0 + 2.0 * x1 - 0.5 * older;
+        let c3 = 0.5 * (older - newer) + 1.5 * (x0 - x1);
+        ((c3 * fraction + c2) * fraction + c1) * fraction + x0
+    }
+}
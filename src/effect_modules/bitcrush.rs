@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::{audio_utils::quantize, automation::argument_values, parse_utils::{keyword_variant, verify_range}, random::Xorshift, types::{AudioBuffer, AudioEffect, Automation}};
+
+/// Rectangular dither spans one step of the new bit depth, triangular (the sum of two) spans two
+#[derive(Clone, Copy)]
+enum Dither {
+    Off,
+    Rect,
+    Tpdf,
+}
+
+impl Dither {
+    const KEYWORDS: &'static [&'static str] = &["off", "rect", "tpdf"];
+    const VARIANTS: [Dither; 3] = [Dither::Off, Dither::Rect, Dither::Tpdf];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<Dither, String> {
+        keyword_variant(value, Dither::KEYWORDS, &Dither::VARIANTS)
+    }
+
+    /// Noise in steps of the new bit depth
+    fn offset(&self, noise: &mut Xorshift) -> f64 {
+        match self {
+            Dither::Off => 0.0,
+            Dither::Rect => noise.next_value() / 2.0,
+            Dither::Tpdf => (noise.next_value() + noise.next_value()) / 2.0,
+        }
+    }
+}
+
+pub struct Bitcrush;
+
+impl Bitcrush {
+    const NAME: &'static str = "bitcrush";
+    const BITS_ARG: &'static str = "bits";
+    const DOWNSAMPLE_ARG: &'static str = "downsample";
+    const JITTER_ARG: &'static str = "jitter";
+    const DITHER_ARG: &'static str = "dither";
+    const MAX_DOWNSAMPLE: f64 = 256.0;
+}
+
+impl AudioEffect for Bitcrush {
+    fn get_name(&self) -> String { Bitcrush::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&Bitcrush::BITS_ARG.to_string(), 1.0, 24.0, arguments)?;
+        verify_range(&Bitcrush::DOWNSAMPLE_ARG.to_string(), 1.0, Bitcrush::MAX_DOWNSAMPLE, arguments)?;
+
+        if arguments.contains_key(Bitcrush::JITTER_ARG) {
+            verify_range(&Bitcrush::JITTER_ARG.to_string(), 0.0, 1.0, arguments)?;
+        }
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let mut arguments = arguments.clone();
+        arguments.entry(Bitcrush::JITTER_ARG.to_string()).or_insert(0.0);
+        let bits_at = argument_values(Bitcrush::BITS_ARG, &arguments, automation);
+        let downsample_at = argument_values(Bitcrush::DOWNSAMPLE_ARG, &arguments, automation);
+        let jitter_at = argument_values(Bitcrush::JITTER_ARG, &arguments, automation);
+        let dither = arguments.get(Bitcrush::DITHER_ARG).map_or(Ok(Dither::Off), |value| Dither::from_argument(*value))?;
+
+        let channels = buffer.channels.len();
+        let mut clock = Xorshift::new(1);
+        let mut noise: Vec<Xorshift> = (0..channels).map(|channel| Xorshift::new(channel as u64 + 2)).collect();
+        let mut held = vec![0.0; channels];
+
+        // All channels share one sample clock, which jitter makes every hold up to `jitter` times longer or shorter
+        let mut hold = 0.0;
+        let mut elapsed = 0.0;
+        for n in 0..buffer.channels[0].len() {
+            if elapsed >= hold {
+                elapsed -= hold;
+                hold = downsample_at(n) * (1.0 + jitter_at(n) * clock.next_value());
+
+                let levels = 2.0_f64.powf(bits_at(n) - 1.0);
+                for (channel, value) in held.iter_mut().enumerate() {
+                    let offset = dither.offset(&mut noise[channel]);
+                    *value = quantize(buffer.channels[channel][n] + offset / levels, levels);
+                }
+            }
+            elapsed += 1.0;
+
+            for (channel, value) in held.iter().enumerate() {
+                buffer.channels[channel][n] = *value;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == Bitcrush::DITHER_ARG).then_some(Dither::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, Bitcrush::BITS_ARG | Bitcrush::DOWNSAMPLE_ARG | Bitcrush::JITTER_ARG)
+    }
+}
@@ -0,0 +1,142 @@
+use core::f64;
+use std::collections::HashMap;
+
+use crate::{parse_utils::{arg_exists, keyword_variant, list_argument, verify_range}, types::{AudioBuffer, AudioEffect}};
+
+const MAX_CHANNELS: f64 = 64.0;
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Remap,
+    Mono,
+    Itu,
+    Duplicate,
+}
+
+impl Mode {
+    const KEYWORDS: &'static [&'static str] = &["remap", "mono", "itu", "duplicate"];
+    const VARIANTS: [Mode; 4] = [Mode::Remap, Mode::Mono, Mode::Itu, Mode::Duplicate];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<Mode, String> {
+        keyword_variant(value, Mode::KEYWORDS, &Mode::VARIANTS)
+    }
+}
+
+/// Gain laws for the mono downmix: average (-6 dB for stereo), equal power (-3 dB) and plain sum (0 dB)
+#[derive(Clone, Copy)]
+enum DownmixLaw {
+    Average,
+    Power,
+    Sum,
+}
+
+impl DownmixLaw {
+    const KEYWORDS: &'static [&'static str] = &["average", "power", "sum"];
+    const VARIANTS: [DownmixLaw; 3] = [DownmixLaw::Average, DownmixLaw::Power, DownmixLaw::Sum];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<DownmixLaw, String> {
+        keyword_variant(value, DownmixLaw::KEYWORDS, &DownmixLaw::VARIANTS)
+    }
+
+    /// Gain applied to the sum of `count` channels
+    fn scale(&self, count: usize) -> f64 {
+        match self {
+            DownmixLaw::Average => 1.0 / count as f64,
+            DownmixLaw::Power => 1.0 / (count as f64).sqrt(),
+            DownmixLaw::Sum => 1.0,
+        }
+    }
+}
+
+pub struct Channels;
+
+impl Channels {
+    const NAME: &'static str = "channels";
+    const MODE_ARG: &'static str = "mode";
+    const MAP_ARG: &'static str = "map";
+    const LAW_ARG: &'static str = "law";
+    const COUNT_ARG: &'static str = "count";
+}
+
+impl AudioEffect for Channels {
+    fn get_name(&self) -> String { Channels::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        let mode = Mode::from_argument(arg_exists(&Channels::MODE_ARG.to_string(), arguments)?)?;
+
+        match mode {
+            Mode::Remap => {
+                let map = list_argument(Channels::MAP_ARG, arguments)?;
+                if map.len() as f64 > MAX_CHANNELS {
+                    return Err(format!("At most {MAX_CHANNELS} output channels are supported"));
+                }
+                if map.iter().any(|index| *index < 0.0 || index.fract() != 0.0) {
+                    return Err(format!("Argument '{}' must only contain channel numbers", Channels::MAP_ARG));
+                }
+            },
+            Mode::Mono => { DownmixLaw::from_argument(arg_exists(&Channels::LAW_ARG.to_string(), arguments)?)?; },
+            Mode::Itu => {},
+            Mode::Duplicate => {
+                let count = verify_range(&Channels::COUNT_ARG.to_string(), 1.0, MAX_CHANNELS, arguments)?;
+                if count.fract() != 0.0 {
+                    return Err(format!("Argument '{}' must be a whole number", Channels::COUNT_ARG));
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let input_count = buffer.channels.len();
+        let length = buffer.channels[0].len();
+
+        let channels: Vec<Vec<f64>> = match Mode::from_argument(*arguments.get(Channels::MODE_ARG).unwrap())? {
+            Mode::Remap => {
+                let map = list_argument(Channels::MAP_ARG, arguments)?;
+                if let Some(index) = map.iter().find(|index| **index as usize >= input_count) {
+                    return Err(format!("Channel {index} doesn't exist, the signal has {input_count} channel(s)"));
+                }
+                map.iter().map(|index| buffer.channels[*index as usize].clone()).collect()
+            },
+            Mode::Mono => {
+                let scale = DownmixLaw::from_argument(*arguments.get(Channels::LAW_ARG).unwrap())?.scale(input_count);
+                let mono = (0..length).map(|n| buffer.channels.iter().map(|channel| channel[n]).sum::<f64>() * scale).collect();
+                vec![mono]
+            },
+            Mode::Itu => {
+                // ITU-R BS.775 downmix of L, R, C, LFE, Ls, Rs (WAV channel order), the LFE is dropped
+                if input_count != 6 {
+                    return Err(format!("The ITU downmix needs a 5.1 signal, the signal has {input_count} channel(s)"));
+                }
+                let c = &buffer.channels;
+                let k = f64::consts::FRAC_1_SQRT_2;
+                let left = (0..length).map(|n| c[0][n] + k * c[2][n] + k * c[4][n]).collect();
+                let right = (0..length).map(|n| c[1][n] + k * c[2][n] + k * c[5][n]).collect();
+                vec![left, right]
+            },
+            Mode::Duplicate => {
+                if input_count != 1 {
+                    return Err(format!("Duplication needs a mono signal, the signal has {input_count} channel(s)"));
+                }
+                let count = *arguments.get(Channels::COUNT_ARG).unwrap() as usize;
+                vec![buffer.channels[0].clone(); count]
+            },
+        };
+
+        buffer.channels = channels;
+        buffer.spec.channels = buffer.channels.len() as u16;
+
+        Ok(Some(format!("{input_count} -> {} channel(s)", buffer.channels.len())))
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        match argument {
+            Channels::MODE_ARG => Some(Mode::KEYWORDS),
+            Channels::LAW_ARG => Some(DownmixLaw::KEYWORDS),
+            _ => None,
+        }
+    }
+}
@@ -1,18 +1,117 @@
+use core::f64;
 use std::collections::HashMap;
-use ringbuffer::{AllocRingBuffer, RingBuffer};
-use crate::types::{AudioBuffer, AudioEffect};
-use crate::parse_utils::verify_min;
+use crate::types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients};
+use crate::parse_utils::{arg_exists, keyword_index, list_argument, verify_min, verify_range};
+use crate::audio_utils::SILENCE_RMS;
+use crate::automation::{argument_values, arguments_at, AUTOMATION_BLOCK};
+use crate::biquad::BiquadCascade;
+use crate::delay_line::DelayLine;
+use crate::effect_modules::filter::{design_sections, FilterType};
 
 pub struct Delay;
 
-const MIN_DELAY_ENERGY: f64 = 0.0001; // This is equivalent to -80 dB in energy
 const MAX_TAIL_LENGTH_SECONDS: i32 = 3600;
-                                      
+const MAX_BPM: f64 = 1000.0;
+
+/// Note lengths for tempo synced delays: plain, dotted and triplet
+const DIVISIONS: &[&str] = &[
+    "1/1", "1/1d", "1/1t", "1/2", "1/2d", "1/2t", "1/4", "1/4d", "1/4t",
+    "1/8", "1/8d", "1/8t", "1/16", "1/16d", "1/16t", "1/32", "1/32d", "1/32t",
+];
+
 impl Delay {
     const NAME: &str = "delay";
     const WET_ARG: &str = "wet";
     const FEEDBACK_ARG: &str = "fb";
     const TIME_ARG: &str = "time";
+    const BPM_ARG: &str = "bpm";
+    const DIVISION_ARG: &str = "division";
+    const MODE_ARG: &str = "mode";
+    const LOWCUT_ARG: &str = "lowcut";
+    const HIGHCUT_ARG: &str = "highcut";
+    const TAP_ARG: &str = "tap";
+    const TAP_DB_ARG: &str = "tapdb";
+    const TAP_PAN_ARG: &str = "tappan";
+    const MODES: &[&str] = &["normal", "pingpong"];
+
+    /// Delay times in milliseconds, either one for all channels or one per channel
+    fn times(arguments: &HashMap<String, f64>) -> Result<Vec<f64>, String> {
+        match arguments.get(Delay::BPM_ARG) {
+            Some(bpm) => {
+                let division = keyword_index(arg_exists(&Delay::DIVISION_ARG.to_string(), arguments)?, DIVISIONS)?;
+                let note = 240000.0 / bpm / 2.0_f64.powi((division / 3) as i32);
+                Ok(vec![note * [1.0, 1.5, 2.0 / 3.0][division % 3]])
+            },
+            None => list_argument(Delay::TIME_ARG, arguments),
+        }
+    }
+
+    /// The filters sit in the feedback loop, so every echo is filtered once more than the previous one
+    fn feedback_sections(arguments: &HashMap<String, f64>, sample_rate: u32) -> Vec<BiquadCoefficients> {
+        let mut sections = Vec::new();
+        if let Some(freq) = arguments.get(Delay::LOWCUT_ARG) {
+            sections.extend(design_sections(FilterType::Butterworth, 2, 0.0, *freq, sample_rate, true));
+        }
+        if let Some(freq) = arguments.get(Delay::HIGHCUT_ARG) {
+            sections.extend(design_sections(FilterType::Butterworth, 2, 0.0, *freq, sample_rate, false));
+        }
+        sections
+    }
+
+    /// Extra taps as (time in milliseconds, gain in dB, pan), only read from the dry signal
+    fn taps(arguments: &HashMap<String, f64>) -> Result<Vec<(f64, f64, Option<f64>)>, String> {
+        let Ok(times) = list_argument(Delay::TAP_ARG, arguments) else {
+            return Ok(Vec::new());
+        };
+
+        let gains = list_argument(Delay::TAP_DB_ARG, arguments)?;
+        let pans = list_argument(Delay::TAP_PAN_ARG, arguments).ok();
+        if gains.len() != times.len() || pans.as_ref().is_some_and(|pans| pans.len() != times.len()) {
+            return Err(format!("Arguments '{}', '{}' and '{}' need one value per tap", Delay::TAP_ARG, Delay::TAP_DB_ARG, Delay::TAP_PAN_ARG));
+        }
+
+        Ok((0..times.len()).map(|i| (times[i], gains[i], pans.as_ref().map(|pans| pans[i]))).collect())
+    }
+}
+
+/// Everything that carries over from one frame (a sample of every channel) to the next
+struct DelayState {
+    lines: Vec<DelayLine>,
+    delays: Vec<f64>,
+    filters: BiquadCascade,
+    pingpong: bool,
+    /// Dry signal history for the taps, with their delay in samples and gain per channel
+    history: Vec<DelayLine>,
+    taps: Vec<(f64, Vec<f64>)>,
+    delayed: Vec<f64>,
+}
+
+impl DelayState {
+    fn frame(&mut self, input: &[f64], output: &mut [f64], wet: f64, feedback: f64) {
+        let channels = input.len();
+
+        for (channel, delayed) in self.delayed.iter_mut().enumerate() {
+            *delayed = self.filters.process_sample(channel, self.lines[channel].read(self.delays[channel] - 1.0));
+        }
+
+        // Ping-pong sends the mono sum to the first channel, every echo comes back on the next one
+        let mono = input.iter().sum::<f64>() / channels as f64;
+        for channel in 0..channels {
+            let (line_input, returned) = match self.pingpong {
+                true => (if channel == 0 { mono } else { 0.0 }, self.delayed[(channel + channels - 1) % channels]),
+                false => (input[channel], self.delayed[channel]),
+            };
+            self.lines[channel].push(line_input + feedback * returned);
+            output[channel] = input[channel] + wet * self.delayed[channel];
+        }
+
+        for (channel, history) in self.history.iter_mut().enumerate() {
+            for (delay, gains) in self.taps.iter() {
+                output[channel] += gains[channel] * history.read(delay - 1.0);
+            }
+            history.push(input[channel]);
+        }
+    }
 }
 
 impl AudioEffect for Delay {
@@ -21,73 +120,162 @@ impl AudioEffect for Delay {
 
     fn validate_arguments(&self, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<(), String> {
         verify_min(&Delay::WET_ARG.to_string(), 0.0, arguments)?;
-        verify_min(&Delay::TIME_ARG.to_string(), 1.0, arguments)?;
         let feedback = verify_min(&Delay::FEEDBACK_ARG.to_string(), 0.0, arguments)?;
 
+        if arguments.contains_key(Delay::BPM_ARG) {
+            if list_argument(Delay::TIME_ARG, arguments).is_ok() {
+                return Err(format!("Use either '{}' or '{}' and '{}'", Delay::TIME_ARG, Delay::BPM_ARG, Delay::DIVISION_ARG));
+            }
+            verify_range(&Delay::BPM_ARG.to_string(), 1.0, MAX_BPM, arguments)?;
+            arg_exists(&Delay::DIVISION_ARG.to_string(), arguments)?;
+        } else if list_argument(Delay::TIME_ARG, arguments)?.iter().any(|time| *time < 1.0) {
+            return Err(format!("Argument '{}' must be >= 1", Delay::TIME_ARG));
+        }
+
         if feedback >= 1.0 && tail_length.is_none() {
             return Err("Tail length (--tail, -t) is required for feedback >= 1 to avoid infinite feedback cycles".to_string());
         }
 
+        for cutoff in [Delay::LOWCUT_ARG, Delay::HIGHCUT_ARG] {
+            if arguments.contains_key(cutoff) {
+                verify_range(&cutoff.to_string(), 1.0, 20000.0, arguments)?;
+            }
+        }
+
+        for (time, _, pan) in Delay::taps(arguments)? {
+            if time < 1.0 {
+                return Err(format!("Argument '{}' must be >= 1", Delay::TAP_ARG));
+            }
+            if pan.is_some_and(|pan| !(-1.0..=1.0).contains(&pan)) {
+                return Err(format!("Argument '{}' must be in range [-1, 1]", Delay::TAP_PAN_ARG));
+            }
+        }
+
         Ok(())
     }
 
     fn apply_effect(&self, audio_buffer: &mut AudioBuffer, arguments: &HashMap<String,f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
-        let wet = arguments.get(Delay::WET_ARG).unwrap();
-        let feedback = arguments.get(Delay::FEEDBACK_ARG).unwrap();
-        let time = arguments.get(Delay::TIME_ARG).unwrap();
-        let sample_rate = audio_buffer.spec.sample_rate as i32;
-        
-        for channel in audio_buffer.channels.iter_mut() {
-            let ringbuffer_size = ((time / 1000.0) * sample_rate as f64) as usize;
-            let mut buffer: AllocRingBuffer<f64> = AllocRingBuffer::new(ringbuffer_size);
-        
-            for _ in 0..ringbuffer_size {
-                buffer.enqueue(0.0);
+        self.apply_automated(audio_buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, audio_buffer: &mut AudioBuffer, arguments: &HashMap<String,f64>, automation: &Automation, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let wet_at = argument_values(Delay::WET_ARG, arguments, automation);
+        let feedback_at = argument_values(Delay::FEEDBACK_ARG, arguments, automation);
+        let sample_rate = audio_buffer.spec.sample_rate;
+        let channels = audio_buffer.channels.len();
+        let to_samples = |ms: f64| ms / 1000.0 * sample_rate as f64;
+
+        let times = Delay::times(arguments)?;
+        if times.len() != 1 && times.len() != channels {
+            return Err(format!("Got {} delay times for {channels} channel(s)", times.len()));
+        }
+        let delays: Vec<f64> = (0..channels).map(|channel| to_samples(times[channel % times.len()])).collect();
+
+        let pingpong = match arguments.get(Delay::MODE_ARG) {
+            Some(mode) => Delay::MODES[keyword_index(*mode, Delay::MODES)?] == "pingpong",
+            None => false,
+        };
+        if pingpong && channels < 2 {
+            return Err("Ping-pong needs at least two channels".to_string());
+        }
+
+        // Taps are panned with the constant power law on stereo signals
+        let taps = Delay::taps(arguments)?;
+        if taps.iter().any(|(_, _, pan)| pan.is_some()) && channels != 2 {
+            return Err(format!("Panned taps need a stereo signal, the signal has {channels} channel(s)"));
+        }
+        let taps: Vec<(f64, Vec<f64>)> = taps.iter().map(|(time, db, pan)| {
+            let gain = 10.0_f64.powf(db / 20.0);
+            let gains = match pan {
+                Some(pan) => {
+                    let theta = (pan + 1.0) * f64::consts::FRAC_PI_4;
+                    vec![gain * theta.cos(), gain * theta.sin()]
+                },
+                None => vec![gain; channels],
+            };
+            (to_samples(*time), gains)
+        }).collect();
+
+        let longest_delay = delays.iter().fold(0.0_f64, |a, b| a.max(*b)).ceil() as usize + 1;
+        let longest_tap = taps.iter().fold(0.0_f64, |a, (time, _)| a.max(*time)).ceil() as usize + 1;
+        let mut state = DelayState {
+            lines: (0..channels).map(|_| DelayLine::new(longest_delay)).collect(),
+            delays,
+            filters: BiquadCascade::new(&Delay::feedback_sections(arguments, sample_rate), channels),
+            pingpong,
+            history: if taps.is_empty() { Vec::new() } else { (0..channels).map(|_| DelayLine::new(longest_tap)).collect() },
+            taps,
+            delayed: vec![0.0; channels],
+        };
+
+        let length = audio_buffer.channels[0].len();
+        let filters_automated = automation.contains_key(Delay::LOWCUT_ARG) || automation.contains_key(Delay::HIGHCUT_ARG);
+        let mut input = vec![0.0; channels];
+        let mut output = vec![0.0; channels];
+        for n in 0..length {
+            if filters_automated && n % AUTOMATION_BLOCK == 0 {
+                state.filters.set_coefficients(&Delay::feedback_sections(&arguments_at(n, arguments, automation), sample_rate));
             }
-        
-            for sample in channel.iter_mut() {
-                let delayed = *buffer.front().unwrap();
-                buffer.enqueue(*sample + delayed * feedback);
-                *sample += delayed * wet;
+            for (channel, value) in input.iter_mut().enumerate() {
+                *value = audio_buffer.channels[channel][n];
             }
-        
-            let mut square_sum= buffer.iter().map(|val| val * val).sum::<f64>();
-            let normalizing_factor = (1.0/ringbuffer_size as f64).sqrt();
-            
-
-            if tail_length.is_some() {
-                let length = tail_length.unwrap();
-                let tail_samples = length as i32 * sample_rate;
-                for _ in 0 .. tail_samples {
-                    if channel.len() as f64 / audio_buffer.spec.sample_rate as f64 > length {
-                        break;
-                    }
-                    buffer.enqueue(buffer.front().unwrap() * feedback);
-                    channel.push(buffer.front().unwrap() * wet);
+            state.frame(&input, &mut output, wet_at(n), feedback_at(n));
+            for (channel, value) in output.iter().enumerate() {
+                audio_buffer.channels[channel][n] = *value;
+            }
+        }
+
+        // Tails keep the last values of automated arguments
+        let (wet, feedback) = (wet_at(length), feedback_at(length));
+        if filters_automated {
+            state.filters.set_coefficients(&Delay::feedback_sections(&arguments_at(length, arguments, automation), sample_rate));
+        }
+        let silence = vec![0.0; channels];
+        let mut push_tail = |count: usize, channels: &mut Vec<Vec<f64>>| -> f64 {
+            let mut square_sum = 0.0;
+            for _ in 0..count {
+                state.frame(&silence, &mut output, wet, feedback);
+                for (channel, value) in channels.iter_mut().zip(output.iter()) {
+                    channel.push(*value);
+                    square_sum += value * value;
                 }
-            } else {
+            }
+            (square_sum / (count * output.len()) as f64).sqrt()
+        };
+
+        match tail_length {
+            Some(seconds) => {
+                push_tail((seconds * sample_rate as f64) as usize, &mut audio_buffer.channels);
+            },
+            None => {
+                // Blocks as long as the longest delay are added until one of them is silent, which is dropped again
+                let block = longest_delay.max(longest_tap);
+                let max = MAX_TAIL_LENGTH_SECONDS as usize * sample_rate as usize;
                 let mut count = 0;
-                let max = MAX_TAIL_LENGTH_SECONDS * sample_rate;
-                while normalizing_factor * square_sum.sqrt() > MIN_DELAY_ENERGY {
+                while push_tail(block, &mut audio_buffer.channels) > SILENCE_RMS {
+                    count += block;
                     if count >= max {
                         return Ok(Some("maximum tail length reached!".to_string()));
                     }
-
-                    let front_val = *buffer.front().unwrap();
-                    square_sum -= front_val * front_val;
-                    
-                    buffer.enqueue(front_val * feedback);
-
-                    let back_val = buffer.back().unwrap();
-                    square_sum += back_val * back_val;
-
-                    channel.push(buffer.front().unwrap() * wet);
-
-                    count += 1;
+                }
+                for channel in audio_buffer.channels.iter_mut() {
+                    channel.truncate(channel.len() - block);
                 }
             }
         }
 
         Ok(None)
-    } 
-}
\ No newline at end of file
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        match argument {
+            Delay::DIVISION_ARG => Some(DIVISIONS),
+            Delay::MODE_ARG => Some(Delay::MODES),
+            _ => None,
+        }
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, Delay::WET_ARG | Delay::FEEDBACK_ARG | Delay::LOWCUT_ARG | Delay::HIGHCUT_ARG)
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{parse_utils::{time_in_samples, verify_range, verify_time}, types::{AudioBuffer, AudioEffect}};
+
+const START_ARG: &str = "start";
+const END_ARG: &str = "end";
+const MAX_REPEATS: f64 = 1000.0;
+
+/// Start and end of a region in samples. The end is clamped to the length of the buffer.
+fn region(buffer: &AudioBuffer, arguments: &HashMap<String, f64>) -> Result<(usize, usize, bool), String> {
+    let sample_rate = buffer.spec.sample_rate;
+    let length = buffer.channels[0].len();
+    let start = time_in_samples(START_ARG, arguments, sample_rate);
+    let end = time_in_samples(END_ARG, arguments, sample_rate);
+
+    if start >= length {
+        return Err(format!("'{START_ARG}' is past the end of the audio ({length} samples)"));
+    }
+    if end <= start {
+        return Err(format!("'{END_ARG}' must come after '{START_ARG}'"));
+    }
+    Ok((start, end.min(length), end > length))
+}
+
+fn validate_region(arguments: &HashMap<String, f64>) -> Result<(), String> {
+    verify_time(START_ARG, arguments)?;
+    verify_time(END_ARG, arguments)?;
+    Ok(())
+}
+
+pub struct Trim;
+
+impl Trim {
+    const NAME: &'static str = "trim";
+}
+
+impl AudioEffect for Trim {
+    fn get_name(&self) -> String { Trim::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_region(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let (start, end, clamped) = region(buffer, arguments)?;
+
+        for channel in buffer.channels.iter_mut() {
+            channel.truncate(end);
+            channel.drain(..start);
+        }
+
+        Ok(clamped.then(|| format!("'{END_ARG}' is past the end of the audio, kept everything after '{START_ARG}'")))
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[START_ARG, END_ARG]
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
+
+pub struct Cut;
+
+impl Cut {
+    const NAME: &'static str = "cut";
+}
+
+impl AudioEffect for Cut {
+    fn get_name(&self) -> String { Cut::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_region(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let (start, end, clamped) = region(buffer, arguments)?;
+
+        for channel in buffer.channels.iter_mut() {
+            channel.drain(start..end);
+        }
+
+        Ok(clamped.then(|| format!("'{END_ARG}' is past the end of the audio, removed everything after '{START_ARG}'")))
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[START_ARG, END_ARG]
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
+
+pub struct Pad;
+
+impl Pad {
+    const NAME: &'static str = "pad";
+    const BEFORE_ARG: &'static str = "before";
+    const AFTER_ARG: &'static str = "after";
+}
+
+impl AudioEffect for Pad {
+    fn get_name(&self) -> String { Pad::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_time(Pad::BEFORE_ARG, arguments)?;
+        verify_time(Pad::AFTER_ARG, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let before = time_in_samples(Pad::BEFORE_ARG, arguments, buffer.spec.sample_rate);
+        let after = time_in_samples(Pad::AFTER_ARG, arguments, buffer.spec.sample_rate);
+
+        for channel in buffer.channels.iter_mut() {
+            channel.splice(0..0, std::iter::repeat_n(0.0, before));
+            channel.resize(channel.len() + after, 0.0);
+        }
+
+        Ok(None)
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[Pad::BEFORE_ARG, Pad::AFTER_ARG]
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
+
+pub struct Reverse;
+
+impl Reverse {
+    const NAME: &'static str = "reverse";
+}
+
+impl AudioEffect for Reverse {
+    fn get_name(&self) -> String { Reverse::NAME.to_string() }
+
+    fn validate_arguments(&self, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        for channel in buffer.channels.iter_mut() {
+            channel.reverse();
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct Repeat;
+
+impl Repeat {
+    const NAME: &'static str = "repeat";
+    const COUNT_ARG: &'static str = "count";
+}
+
+impl AudioEffect for Repeat {
+    fn get_name(&self) -> String { Repeat::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        let count = verify_range(&Repeat::COUNT_ARG.to_string(), 1.0, MAX_REPEATS, arguments)?;
+        if count.fract() != 0.0 {
+            return Err(format!("Argument '{}' must be a whole number", Repeat::COUNT_ARG));
+        }
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let count = *arguments.get(Repeat::COUNT_ARG).unwrap() as usize;
+
+        for channel in buffer.channels.iter_mut() {
+            *channel = channel.repeat(count);
+        }
+
+        Ok(None)
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
@@ -1,34 +1,98 @@
 use core::f64;
 use std::collections::HashMap;
 
-use crate::{parse_utils::{arg_exists, verify_range, verify_min}, types::{AudioBuffer, AudioEffect}};
+use crate::{biquad::apply_automated_biquads, parse_utils::{arg_exists, keyword_variant, verify_range, verify_min}, types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients}};
+
+/// Filter shapes of the RBJ audio EQ cookbook
+#[derive(Clone, Copy)]
+pub enum BandShape {
+    Peak,
+    LowShelf,
+    HighShelf,
+    Notch,
+    BandPass,
+    AllPass,
+    LowPass,
+    HighPass,
+}
 
-pub struct PeakingEQ;
+impl BandShape {
+    pub const KEYWORDS: &'static [&'static str] = &["peak", "lowshelf", "highshelf", "notch", "bandpass", "allpass", "lowpass", "highpass"];
+    const VARIANTS: [BandShape; 8] = [BandShape::Peak, BandShape::LowShelf, BandShape::HighShelf, BandShape::Notch,
+        BandShape::BandPass, BandShape::AllPass, BandShape::LowPass, BandShape::HighPass];
 
-fn apply_df1(buffer: &mut AudioBuffer, a0:f64, a1:f64, a2:f64, b0:f64, b1:f64, b2:f64) {
-    for channel in buffer.channels.iter_mut() {
-        let mut x1 = 0.0;  // x[n-1]
-        let mut x2 = 0.0;  // x[n-2]
-        let mut y1 = 0.0;  // y[n-1] 
-        let mut y2 = 0.0;  // y[n-2]
+    /// Keyword arguments hold the index of their keyword
+    pub fn from_argument(value: f64) -> Result<BandShape, String> {
+        keyword_variant(value, BandShape::KEYWORDS, &BandShape::VARIANTS)
+    }
+
+    /// Only peaks and shelves have a gain
+    pub fn has_gain(&self) -> bool {
+        matches!(self, BandShape::Peak | BandShape::LowShelf | BandShape::HighShelf)
+    }
 
-        for sample in channel {
-            let x0 = *sample;
-            let y0 = (b0/a0) * x0 + (b1/a0) * x1 + (b2/a0) * x2 - (a1/a0) * y1 - (a2/a0) * y2;
-            *sample = y0;  
-            x2 = x1;
-            x1 = x0;
-            y2 = y1;
-            y1 = y0;
-        }
+    /// RBJ cookbook coefficients, with the width of every shape given as Q
+    pub fn coefficients(&self, f0: f64, db_gain: f64, q: f64, sample_rate: u32) -> BiquadCoefficients {
+        let fs = sample_rate as f64;
+        let a = 10.0_f64.powf(db_gain / 40.0);
+        let w0 = 2.0 * f64::consts::PI * (f0 / fs);
+        let sinw0 = w0.sin();
+        let cosw0 = w0.cos();
+        let alpha = sinw0 / (2.0 * q);
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match self {
+            BandShape::Peak => (1.0 + alpha * a, -2.0 * cosw0, 1.0 - alpha * a, 1.0 + alpha / a, -2.0 * cosw0, 1.0 - alpha / a),
+            BandShape::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cosw0 + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cosw0),
+                a * ((a + 1.0) - (a - 1.0) * cosw0 - shelf),
+                (a + 1.0) + (a - 1.0) * cosw0 + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cosw0),
+                (a + 1.0) + (a - 1.0) * cosw0 - shelf,
+            ),
+            BandShape::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cosw0 + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cosw0),
+                a * ((a + 1.0) + (a - 1.0) * cosw0 - shelf),
+                (a + 1.0) - (a - 1.0) * cosw0 + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cosw0),
+                (a + 1.0) - (a - 1.0) * cosw0 - shelf,
+            ),
+            BandShape::Notch => (1.0, -2.0 * cosw0, 1.0, 1.0 + alpha, -2.0 * cosw0, 1.0 - alpha),
+            BandShape::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cosw0, 1.0 - alpha),
+            BandShape::AllPass => (1.0 - alpha, -2.0 * cosw0, 1.0 + alpha, 1.0 + alpha, -2.0 * cosw0, 1.0 - alpha),
+            BandShape::LowPass => ((1.0 - cosw0) / 2.0, 1.0 - cosw0, (1.0 - cosw0) / 2.0, 1.0 + alpha, -2.0 * cosw0, 1.0 - alpha),
+            BandShape::HighPass => ((1.0 + cosw0) / 2.0, -(1.0 + cosw0), (1.0 + cosw0) / 2.0, 1.0 + alpha, -2.0 * cosw0, 1.0 - alpha),
+        };
+
+        BiquadCoefficients { b0, b1, b2, a0, a1, a2 }
     }
 }
 
+/// Q of a shelf with slope `s` and gain `db_gain`
+fn shelf_q(db_gain: f64, s: f64) -> f64 {
+    let a = 10.0_f64.powf(db_gain / 40.0);
+    1.0 / ((a + 1.0 / a) * (1.0 / s - 1.0) + 2.0).sqrt()
+}
+
+pub struct PeakingEQ;
+
 impl PeakingEQ {
     pub const NAME: &'static str = "peakingeq";
     pub const DB_ARG: &'static str = "db";
     pub const FREQ_ARG: &'static str = "freq";
     pub const BW_ARG: &'static str = "bw";
+
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
+        let bw = *arguments.get(PeakingEQ::BW_ARG).unwrap();
+
+        let w0 = 2.0 * f64::consts::PI * (f0 / sample_rate as f64);
+        let q = 1.0 / (2.0 * (2.0_f64.log2() / 2.0 * bw * (w0 / w0.sin())).sinh());
+        BandShape::Peak.coefficients(f0, db_gain, q, sample_rate)
+    }
 }
 
 impl AudioEffect for PeakingEQ {
@@ -44,30 +108,25 @@ impl AudioEffect for PeakingEQ {
         Ok(())
     }
 
-    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
-        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
-        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
-        let bw = *arguments.get(PeakingEQ::BW_ARG).unwrap();
-        
-        let fs = buffer.spec.sample_rate as f64;
-        let a= 10.0_f64.powf(db_gain/40.0);
-        let w0 = 2.0 * f64::consts::PI * (f0/fs);
-        let sinw0 = w0.sin();
-        let cosw0 = w0.cos();
-        let alpha = sinw0 * (2.0_f64.log2()/2.0 * bw * (w0/sinw0)).sinh();
-
-        let b0 = 1.0 + alpha * a;
-        let b1 = -2.0 * cosw0;
-        let b2 = 1.0 - alpha * a;
-        let a0 = 1.0 + alpha/a;
-        let a1 = -2.0 * cosw0;
-        let a2 = 1.0 - alpha/a;
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
 
-        apply_df1(buffer, a0, a1, a2, b0, b1, b2);
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![PeakingEQ::coefficients(values, sample_rate)]);
 
         Ok(None)
     }
 
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![PeakingEQ::coefficients(arguments, sample_rate)])
+    }
+
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | PeakingEQ::DB_ARG | PeakingEQ::BW_ARG)
+    }
 }
 
 pub struct HShelfEQ;
@@ -75,6 +134,14 @@ pub struct HShelfEQ;
 impl HShelfEQ {
     pub const NAME: &'static str = "hshelfeq";
     pub const S_ARG: &'static str = "s";
+
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
+        let s = *arguments.get(HShelfEQ::S_ARG).unwrap();
+
+        BandShape::HighShelf.coefficients(f0, db_gain, shelf_q(db_gain, s), sample_rate)
+    }
 }
 
 impl AudioEffect for HShelfEQ {
@@ -90,29 +157,23 @@ impl AudioEffect for HShelfEQ {
         Ok(())
     }
 
-    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
-
-        let fs = buffer.spec.sample_rate as f64;
-        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
-        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
-        let s = *arguments.get(HShelfEQ::S_ARG).unwrap();
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
 
-        let a= 10.0_f64.powf(db_gain/40.0);
-        let w0 = 2.0 * f64::consts::PI * (f0/fs);
-        let sinw0 = w0.sin();
-        let cosw0 = w0.cos();
-        let alpha = (sinw0 / 2.0) * ((a + 1.0/a) * (1.0/s - 1.0) + 2.0).sqrt();
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![HShelfEQ::coefficients(values, sample_rate)]);
 
-        let b0 = a * ((a + 1.0) + (a - 1.0) * cosw0 + 2.0 * a.sqrt() * alpha);
-        let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cosw0);
-        let b2 = a * ((a+1.0) + (a - 1.0) * cosw0 - 2.0 * a.sqrt() * alpha);
-        let a0 = (a + 1.0) - (a-1.0) * cosw0 + 2.0 * a.sqrt() * alpha;
-        let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cosw0);
-        let a2 = (a + 1.0) - (a - 1.0) * cosw0 - 2.0 * a.sqrt() * alpha;
+        Ok(None)
+    }
 
-        apply_df1(buffer, a0, a1, a2, b0, b1, b2);
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![HShelfEQ::coefficients(arguments, sample_rate)])
+    }
 
-        Ok(None)
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | PeakingEQ::DB_ARG | HShelfEQ::S_ARG)
     }
 }
 
@@ -120,6 +181,14 @@ pub struct LShelfEQ;
 
 impl LShelfEQ {
     pub const NAME: &'static str = "lshelfeq";
+
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
+        let s = *arguments.get(HShelfEQ::S_ARG).unwrap();
+
+        BandShape::LowShelf.coefficients(f0, db_gain, shelf_q(db_gain, s), sample_rate)
+    }
 }
 
 impl AudioEffect for LShelfEQ {
@@ -135,30 +204,23 @@ impl AudioEffect for LShelfEQ {
         Ok(())
     }
 
-    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
-
-        let fs = buffer.spec.sample_rate as f64;
-        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
-        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
-        let s = *arguments.get(HShelfEQ::S_ARG).unwrap();
-
-        let a= 10.0_f64.powf(db_gain/40.0);
-        let w0 = 2.0 * f64::consts::PI * (f0/fs);
-        let sinw0 = w0.sin();
-        let cosw0 = w0.cos();
-        let alpha = (sinw0 / 2.0) * ((a + 1.0/a) * (1.0/s - 1.0) + 2.0).sqrt();
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
 
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![LShelfEQ::coefficients(values, sample_rate)]);
 
-        let b0 = a * ((a + 1.0) - (a - 1.0) * cosw0 + 2.0 * a.sqrt() * alpha);
-        let b1 = 2.0 * a * ((a - 1.0) - (a + 1.0) * cosw0);
-        let b2 = a * ((a+1.0) - (a - 1.0) * cosw0 - 2.0 * a.sqrt() * alpha);
-        let a0 = (a + 1.0) + (a-1.0) * cosw0 + 2.0 * a.sqrt() * alpha;
-        let a1 = -2.0 * ((a - 1.0) + (a + 1.0) * cosw0);
-        let a2 = (a + 1.0) + (a - 1.0) * cosw0 - 2.0 * a.sqrt() * alpha;
+        Ok(None)
+    }
 
-        apply_df1(buffer, a0, a1, a2, b0, b1, b2);
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![LShelfEQ::coefficients(arguments, sample_rate)])
+    }
 
-        Ok(None)
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | PeakingEQ::DB_ARG | HShelfEQ::S_ARG)
     }
 }
 
@@ -168,6 +230,13 @@ pub struct BandPassEQ;
 impl BandPassEQ {
     pub const NAME: &'static str = "bandpasseq";
     pub const Q_ARG: &'static str = "q";
+
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+        let q = *arguments.get(BandPassEQ::Q_ARG).unwrap();
+
+        BandShape::BandPass.coefficients(f0, 0.0, q, sample_rate)
+    }
 }
 
 impl AudioEffect for BandPassEQ {
@@ -182,26 +251,167 @@ impl AudioEffect for BandPassEQ {
         Ok(())
     }
 
-    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![BandPassEQ::coefficients(values, sample_rate)]);
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![BandPassEQ::coefficients(arguments, sample_rate)])
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | BandPassEQ::Q_ARG)
+    }
+}
+
+pub struct NotchEQ;
+
+impl NotchEQ {
+    pub const NAME: &'static str = "notch";
+
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+        let q = *arguments.get(BandPassEQ::Q_ARG).unwrap();
+
+        BandShape::Notch.coefficients(f0, 0.0, q, sample_rate)
+    }
+}
+
+impl AudioEffect for NotchEQ {
+
+    fn get_name(&self) -> String {
+        NotchEQ::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&PeakingEQ::FREQ_ARG.to_string(), 1.0, 20000.0, arguments)?;
+        verify_min(&BandPassEQ::Q_ARG.to_string(), 0.01, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![NotchEQ::coefficients(values, sample_rate)]);
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![NotchEQ::coefficients(arguments, sample_rate)])
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | BandPassEQ::Q_ARG)
+    }
+}
+
+pub struct AllPassEQ;
 
-        let fs = buffer.spec.sample_rate as f64;
+impl AllPassEQ {
+    pub const NAME: &'static str = "allpass";
+
+    pub fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> BiquadCoefficients {
         let f0 = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
         let q = *arguments.get(BandPassEQ::Q_ARG).unwrap();
 
-        let w0 = 2.0 * f64::consts::PI * (f0/fs);
-        let sinw0 = w0.sin();
-        let cosw0 = w0.cos();
-        let alpha = sinw0 /(2.0 * q);
+        BandShape::AllPass.coefficients(f0, 0.0, q, sample_rate)
+    }
+}
+
+impl AudioEffect for AllPassEQ {
+
+    fn get_name(&self) -> String {
+        AllPassEQ::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&PeakingEQ::FREQ_ARG.to_string(), 1.0, 20000.0, arguments)?;
+        verify_min(&BandPassEQ::Q_ARG.to_string(), 0.01, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| vec![AllPassEQ::coefficients(values, sample_rate)]);
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![AllPassEQ::coefficients(arguments, sample_rate)])
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | BandPassEQ::Q_ARG)
+    }
+}
+
+pub struct TiltEQ;
+
+impl TiltEQ {
+    pub const NAME: &'static str = "tilt";
+    const SHELF_SLOPE: f64 = 0.5;
+
+    /// A low shelf and a high shelf around the same pivot, each with half of the gain in opposite directions
+    fn coefficients(arguments: &HashMap<String, f64>, sample_rate: u32) -> Vec<BiquadCoefficients> {
+        let db_gain = *arguments.get(PeakingEQ::DB_ARG).unwrap();
+
+        let mut shelf_arguments = arguments.clone();
+        shelf_arguments.insert(HShelfEQ::S_ARG.to_string(), TiltEQ::SHELF_SLOPE);
+
+        shelf_arguments.insert(PeakingEQ::DB_ARG.to_string(), -db_gain / 2.0);
+        let low = LShelfEQ::coefficients(&shelf_arguments, sample_rate);
 
-        let b0 = alpha;
-        let b1 = 0.0;
-        let b2 = -alpha;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cosw0;
-        let a2 = 1.0 - alpha;
+        shelf_arguments.insert(PeakingEQ::DB_ARG.to_string(), db_gain / 2.0);
+        let high = HShelfEQ::coefficients(&shelf_arguments, sample_rate);
 
-        apply_df1(buffer, a0, a1, a2, b0, b1, b2);
+        vec![low, high]
+    }
+}
+
+impl AudioEffect for TiltEQ {
+
+    fn get_name(&self) -> String {
+        TiltEQ::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        arg_exists(&PeakingEQ::DB_ARG.to_string(), arguments)?;
+        verify_range(&PeakingEQ::FREQ_ARG.to_string(), 1.0, 20000.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        apply_automated_biquads(buffer, arguments, automation, |values| TiltEQ::coefficients(values, sample_rate));
 
         Ok(None)
     }
-}
\ No newline at end of file
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(TiltEQ::coefficients(arguments, sample_rate))
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | PeakingEQ::DB_ARG)
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::{audio_utils::FadeCurve, parse_utils::{arg_exists, time_in_samples, verify_time}, types::{AudioBuffer, AudioEffect}};
+
+const DURATION_ARG: &str = "duration";
+const CURVE_ARG: &str = "curve";
+
+fn validate_fade(arguments: &HashMap<String, f64>) -> Result<(), String> {
+    verify_time(DURATION_ARG, arguments)?;
+    FadeCurve::from_argument(arg_exists(&CURVE_ARG.to_string(), arguments)?)?;
+    Ok(())
+}
+
+/// Applies the fade curve over `duration` samples from the start of the buffer, or towards its end
+fn apply_fade(buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, fade_in: bool) -> Result<Option<String>, String> {
+    let curve = FadeCurve::from_argument(*arguments.get(CURVE_ARG).unwrap())?;
+    let length = buffer.channels[0].len();
+    let requested = time_in_samples(DURATION_ARG, arguments, buffer.spec.sample_rate);
+    let duration = requested.min(length);
+
+    for channel in buffer.channels.iter_mut() {
+        for n in 0..duration {
+            let position = n as f64 / duration as f64;
+            if fade_in {
+                channel[n] *= curve.gain(position);
+            } else {
+                channel[length - 1 - n] *= curve.gain(position);
+            }
+        }
+    }
+
+    Ok((requested > length).then(|| "fade is longer than the audio, faded all of it".to_string()))
+}
+
+pub struct FadeIn;
+
+impl FadeIn {
+    const NAME: &'static str = "fadein";
+}
+
+impl AudioEffect for FadeIn {
+    fn get_name(&self) -> String { FadeIn::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_fade(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        apply_fade(buffer, arguments, true)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == CURVE_ARG).then_some(FadeCurve::KEYWORDS)
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[DURATION_ARG]
+    }
+}
+
+pub struct FadeOut;
+
+impl FadeOut {
+    const NAME: &'static str = "fadeout";
+}
+
+impl AudioEffect for FadeOut {
+    fn get_name(&self) -> String { FadeOut::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_fade(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        apply_fade(buffer, arguments, false)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == CURVE_ARG).then_some(FadeCurve::KEYWORDS)
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[DURATION_ARG]
+    }
+}
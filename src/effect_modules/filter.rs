@@ -0,0 +1,296 @@
+use core::f64;
+use std::collections::HashMap;
+use rustfft::num_complex::Complex;
+
+use crate::{biquad::apply_automated_biquads, effect_modules::eq::PeakingEQ, parse_utils::{arg_exists, keyword_variant, verify_min, verify_range}, types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients}};
+
+const MAX_ORDER: f64 = 24.0;
+const MAX_BESSEL_ORDER: f64 = 10.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FilterType {
+    Butterworth,
+    LinkwitzRiley,
+    Bessel,
+    Chebyshev,
+}
+
+impl FilterType {
+    pub const KEYWORDS: &'static [&'static str] = &["butterworth", "linkwitzriley", "bessel", "chebyshev"];
+    const VARIANTS: [FilterType; 4] = [FilterType::Butterworth, FilterType::LinkwitzRiley, FilterType::Bessel, FilterType::Chebyshev];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<FilterType, String> {
+        keyword_variant(value, FilterType::KEYWORDS, &FilterType::VARIANTS)
+    }
+}
+
+/// Poles of a normalized analog lowpass prototype (cutoff at 1 rad/s).
+/// Only the upper half of every conjugate pair is kept, real poles are kept separately.
+struct Prototype {
+    pairs: Vec<Complex<f64>>,
+    reals: Vec<f64>,
+    gain: f64,
+}
+
+fn butterworth_prototype(order: usize) -> Prototype {
+    let pairs = (0..order / 2).map(|k| {
+        let theta = f64::consts::PI * (2 * k + 1) as f64 / (2 * order) as f64;
+        Complex::new(-theta.sin(), theta.cos())
+    }).collect();
+    let reals = if order % 2 == 1 { vec![-1.0] } else { Vec::new() };
+
+    Prototype { pairs, reals, gain: 1.0 }
+}
+
+fn chebyshev_prototype(order: usize, ripple_db: f64) -> Prototype {
+    let epsilon = (10.0_f64.powf(ripple_db / 10.0) - 1.0).sqrt();
+    let mu = (1.0 / epsilon).asinh() / order as f64;
+
+    let pairs = (0..order / 2).map(|k| {
+        let theta = f64::consts::PI * (2 * k + 1) as f64 / (2 * order) as f64;
+        Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+    }).collect();
+    let reals = if order % 2 == 1 { vec![-mu.sinh()] } else { Vec::new() };
+
+    // Even orders start at the bottom of the ripple, so the passband peaks at 0 dB
+    let gain = if order.is_multiple_of(2) { 1.0 / (1.0 + epsilon * epsilon).sqrt() } else { 1.0 };
+
+    Prototype { pairs, reals, gain }
+}
+
+fn bessel_prototype(order: usize) -> Prototype {
+    // Reverse Bessel polynomial, lowest power first
+    let factorial = |n: usize| (1..=n).map(|i| i as f64).product::<f64>();
+    let coefficients: Vec<f64> = (0..=order)
+        .map(|k| factorial(2 * order - k) / (2.0_f64.powi((order - k) as i32) * factorial(k) * factorial(order - k)))
+        .collect();
+
+    let evaluate = |s: Complex<f64>| coefficients.iter().rev().fold(Complex::new(0.0, 0.0), |acc, c| acc * s + c);
+
+    // Durand-Kerner root finding on the monic polynomial
+    let leading = coefficients[order];
+    let mut roots: Vec<Complex<f64>> = (0..order).map(|k| Complex::new(0.4, 0.9).powu(k as u32)).collect();
+    for _ in 0..500 {
+        for i in 0..order {
+            let mut denominator = Complex::new(leading, 0.0);
+            for j in 0..order {
+                if i != j {
+                    denominator *= roots[i] - roots[j];
+                }
+            }
+            let root = roots[i];
+            roots[i] -= evaluate(root) / denominator;
+        }
+    }
+
+    // Scale the poles so the response is 3 dB down at the cutoff
+    let magnitude = |w: f64| coefficients[0] / evaluate(Complex::new(0.0, w)).norm();
+    let (mut low, mut high) = (0.01_f64, 100.0_f64);
+    for _ in 0..100 {
+        let mid = (low * high).sqrt();
+        if magnitude(mid) > f64::consts::FRAC_1_SQRT_2 { low = mid } else { high = mid }
+    }
+    let w3db = (low * high).sqrt();
+
+    let pairs = roots.iter().filter(|r| r.im > 1e-9).map(|r| r / w3db).collect();
+    let reals = roots.iter().filter(|r| r.im.abs() <= 1e-9).map(|r| r.re / w3db).collect();
+
+    Prototype { pairs, reals, gain: 1.0 }
+}
+
+/// Bilinear transform of H(s) = (b0 + b1 s + b2 s^2) / (a0 + a1 s + a2 s^2), with s normalized to the cutoff
+fn bilinear(b: [f64; 3], a: [f64; 3], k: f64) -> BiquadCoefficients {
+    let k2 = k * k;
+    BiquadCoefficients {
+        b0: b[0] * k2 + b[1] * k + b[2],
+        b1: 2.0 * b[0] * k2 - 2.0 * b[2],
+        b2: b[0] * k2 - b[1] * k + b[2],
+        a0: a[0] * k2 + a[1] * k + a[2],
+        a1: 2.0 * a[0] * k2 - 2.0 * a[2],
+        a2: a[0] * k2 - a[1] * k + a[2],
+    }
+}
+
+/// Same as `bilinear` for a first order H(s) = (b0 + b1 s) / (a0 + a1 s)
+fn bilinear_first_order(b: [f64; 2], a: [f64; 2], k: f64) -> BiquadCoefficients {
+    BiquadCoefficients {
+        b0: b[0] * k + b[1],
+        b1: b[0] * k - b[1],
+        b2: 0.0,
+        a0: a[0] * k + a[1],
+        a1: a[0] * k - a[1],
+        a2: 0.0,
+    }
+}
+
+/// Cascaded second order sections of a lowpass or highpass filter
+pub fn design_sections(filter_type: FilterType, order: usize, ripple_db: f64, freq: f64, sample_rate: u32, highpass: bool) -> Vec<BiquadCoefficients> {
+    let prototype = match filter_type {
+        FilterType::Butterworth => butterworth_prototype(order),
+        FilterType::LinkwitzRiley => {
+            // Two Butterworth filters of half the order in series
+            let half = butterworth_prototype(order / 2);
+            Prototype {
+                pairs: half.pairs.iter().chain(half.pairs.iter()).copied().collect(),
+                reals: half.reals.iter().chain(half.reals.iter()).copied().collect(),
+                gain: 1.0,
+            }
+        },
+        FilterType::Bessel => bessel_prototype(order),
+        FilterType::Chebyshev => chebyshev_prototype(order, ripple_db),
+    };
+
+    let k = (f64::consts::PI * freq / sample_rate as f64).tan();
+    let mut sections = Vec::new();
+
+    for pole in prototype.pairs.iter() {
+        let magnitude = pole.norm_sqr();
+        let section = if highpass {
+            bilinear([0.0, 0.0, magnitude], [1.0, -2.0 * pole.re, magnitude], k)
+        } else {
+            bilinear([magnitude, 0.0, 0.0], [magnitude, -2.0 * pole.re, 1.0], k)
+        };
+        sections.push(section);
+    }
+
+    for pole in prototype.reals.iter() {
+        let section = if highpass {
+            bilinear_first_order([0.0, -pole], [1.0, -pole], k)
+        } else {
+            bilinear_first_order([-pole, 0.0], [-pole, 1.0], k)
+        };
+        sections.push(section);
+    }
+
+    if let Some(first) = sections.first_mut() {
+        first.b0 *= prototype.gain;
+        first.b1 *= prototype.gain;
+        first.b2 *= prototype.gain;
+    }
+
+    sections
+}
+
+fn validate_filter(arguments: &HashMap<String, f64>) -> Result<(), String> {
+    verify_range(&PeakingEQ::FREQ_ARG.to_string(), 1.0, 20000.0, arguments)?;
+    let order = verify_range(&HighPass::ORDER_ARG.to_string(), 1.0, MAX_ORDER, arguments)?;
+    let filter_type = FilterType::from_argument(arg_exists(&HighPass::TYPE_ARG.to_string(), arguments)?)?;
+
+    if order.fract() != 0.0 {
+        return Err(format!("Argument '{}' must be a whole number", HighPass::ORDER_ARG));
+    }
+
+    match filter_type {
+        FilterType::LinkwitzRiley if !(order as usize).is_multiple_of(2) => {
+            return Err(format!("Linkwitz-Riley filters need an even '{}'", HighPass::ORDER_ARG));
+        },
+        FilterType::Bessel if order > MAX_BESSEL_ORDER => {
+            return Err(format!("Bessel filters support an '{}' of at most {MAX_BESSEL_ORDER}", HighPass::ORDER_ARG));
+        },
+        FilterType::Chebyshev => {
+            verify_min(&HighPass::RIPPLE_ARG.to_string(), 0.01, arguments)?;
+        },
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// The type is a keyword, so it can't be automated and is resolved once for every design
+fn filter_type(arguments: &HashMap<String, f64>) -> Result<FilterType, String> {
+    FilterType::from_argument(arg_exists(&HighPass::TYPE_ARG.to_string(), arguments)?)
+}
+
+fn filter_sections(filter_type: FilterType, arguments: &HashMap<String, f64>, sample_rate: u32, highpass: bool) -> Vec<BiquadCoefficients> {
+    let freq = *arguments.get(PeakingEQ::FREQ_ARG).unwrap();
+    let order = *arguments.get(HighPass::ORDER_ARG).unwrap() as usize;
+    let ripple = arguments.get(HighPass::RIPPLE_ARG).copied().unwrap_or(0.0);
+
+    design_sections(filter_type, order, ripple, freq, sample_rate, highpass)
+}
+
+pub struct HighPass;
+
+impl HighPass {
+    pub const NAME: &'static str = "highpass";
+    pub const ORDER_ARG: &'static str = "order";
+    pub const TYPE_ARG: &'static str = "type";
+    pub const RIPPLE_ARG: &'static str = "ripple";
+}
+
+impl AudioEffect for HighPass {
+
+    fn get_name(&self) -> String {
+        HighPass::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_filter(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let filter_type = filter_type(arguments)?;
+        apply_automated_biquads(buffer, arguments, automation, |values| filter_sections(filter_type, values, sample_rate, true));
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        filter_type(arguments).ok().map(|filter_type| filter_sections(filter_type, arguments, sample_rate, true))
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == HighPass::TYPE_ARG).then_some(FilterType::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | HighPass::RIPPLE_ARG)
+    }
+}
+
+pub struct LowPass;
+
+impl LowPass {
+    pub const NAME: &'static str = "lowpass";
+}
+
+impl AudioEffect for LowPass {
+
+    fn get_name(&self) -> String {
+        LowPass::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_filter(arguments)
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let filter_type = filter_type(arguments)?;
+        apply_automated_biquads(buffer, arguments, automation, |values| filter_sections(filter_type, values, sample_rate, false));
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        filter_type(arguments).ok().map(|filter_type| filter_sections(filter_type, arguments, sample_rate, false))
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == HighPass::TYPE_ARG).then_some(FilterType::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, PeakingEQ::FREQ_ARG | HighPass::RIPPLE_ARG)
+    }
+}
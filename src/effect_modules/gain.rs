@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::{parse_utils::arg_exists, types::{AudioBuffer, AudioEffect}};
+use crate::{automation::argument_values, parse_utils::arg_exists, types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients}};
 
 pub struct Gain;
 
@@ -16,17 +16,31 @@ impl AudioEffect for Gain {
         Ok(())
     }
 
-    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
-        let db = arguments.get(Gain::DB_ARG).unwrap();
-        let factor = 10.0_f64.powf(db / 20.0);
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let db = argument_values(Gain::DB_ARG, arguments, automation);
+        let factors: Vec<f64> = (0..buffer.channels[0].len()).map(|n| 10.0_f64.powf(db(n) / 20.0)).collect();
 
         for channel in buffer.channels.iter_mut() {
 
-            for sample in channel.iter_mut(){
-                *sample = *sample * factor;
+            for (sample, factor) in channel.iter_mut().zip(factors.iter()) {
+                *sample *= factor;
             };
         }
 
         Ok(None)
     }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, _sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        let db = arguments.get(Gain::DB_ARG).unwrap();
+        let factor = 10.0_f64.powf(db / 20.0);
+        Some(vec![BiquadCoefficients { b0: factor, b1: 0.0, b2: 0.0, a0: 1.0, a1: 0.0, a2: 0.0 }])
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == Gain::DB_ARG
+    }
 }
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use crate::{loudness, parse_utils::{verify_range, verify_max}, types::{AudioBuffer, AudioEffect}};
+
+pub struct LoudNorm;
+
+impl LoudNorm {
+    const NAME: &'static str = "loudnorm";
+    const LUFS_ARG: &'static str = "lufs";
+    const TP_ARG: &'static str = "tp";
+}
+
+impl AudioEffect for LoudNorm {
+    fn get_name(&self) -> String { LoudNorm::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&LoudNorm::LUFS_ARG.to_string(), -70.0, 0.0, arguments)?;
+        verify_max(&LoudNorm::TP_ARG.to_string(), 0.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let target = *arguments.get(LoudNorm::LUFS_ARG).unwrap();
+        let ceiling = *arguments.get(LoudNorm::TP_ARG).unwrap();
+
+        let stats = loudness::measure(buffer);
+        let measured = format!("I: {:.1} LUFS, LRA: {:.1} LU, TP: {:.1} dBTP",
+            stats.integrated, stats.range, 20.0 * stats.true_peak.log10());
+
+        if !stats.integrated.is_finite() {
+            return Ok(Some(format!("{measured}, audio is below the measurement gate")));
+        }
+
+        let mut gain_db = target - stats.integrated;
+        let peak_after = 20.0 * stats.true_peak.log10() + gain_db;
+        let limited = peak_after > ceiling;
+        if limited {
+            gain_db -= peak_after - ceiling;
+        }
+
+        let factor = 10.0_f64.powf(gain_db / 20.0);
+        for channel in buffer.channels.iter_mut() {
+            for sample in channel.iter_mut() {
+                *sample *= factor;
+            }
+        }
+
+        if limited {
+            Ok(Some(format!("{measured}, gain {gain_db:+.1} dB, limited by true peak ceiling to {:.1} LUFS", stats.integrated + gain_db)))
+        } else {
+            Ok(Some(format!("{measured}, gain {gain_db:+.1} dB")))
+        }
+    }
+}
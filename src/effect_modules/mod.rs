@@ -3,3 +3,15 @@ pub mod gain;
 pub mod softclip;
 pub mod normalize;
 pub mod eq;
+pub mod filter;
+pub mod loudnorm;
+pub mod parametric;
+pub mod stereo;
+pub mod channels;
+pub mod edit;
+pub mod fade;
+pub mod silence;
+pub mod modulation;
+pub mod pitch;
+pub mod saturate;
+pub mod bitcrush;
@@ -0,0 +1,318 @@
+use core::f64;
+use std::collections::HashMap;
+
+use crate::{automation::{argument_max, argument_values}, delay_line::DelayLine, lfo::{Lfo, LfoShape}, parse_utils::{arg_exists, verify_min, verify_range}, types::{AudioBuffer, AudioEffect, Automation}};
+
+const RATE_ARG: &str = "rate";
+const DEPTH_ARG: &str = "depth";
+const SHAPE_ARG: &str = "shape";
+const STEREO_ARG: &str = "stereo";
+const WET_ARG: &str = "wet";
+const FEEDBACK_ARG: &str = "fb";
+
+const MAX_RATE: f64 = 100.0;
+/// Longest delay sweep of the delay based effects, in milliseconds
+const MAX_DEPTH_MS: f64 = 50.0;
+const MAX_FEEDBACK: f64 = 0.95;
+
+/// Checks the arguments every modulation effect shares: rate (Hz), LFO shape and stereo phase offset (degrees)
+fn validate_lfo(arguments: &HashMap<String, f64>) -> Result<(), String> {
+    verify_range(&RATE_ARG.to_string(), 0.0, MAX_RATE, arguments)?;
+    LfoShape::from_argument(arg_exists(&SHAPE_ARG.to_string(), arguments)?)?;
+    verify_range(&STEREO_ARG.to_string(), 0.0, 360.0, arguments)?;
+    Ok(())
+}
+
+/// The oscillator of a channel, every channel is `stereo` degrees further in the cycle than the previous one
+fn channel_lfo(arguments: &HashMap<String, f64>, channel: usize, sample_rate: u32) -> Result<Lfo, String> {
+    let shape = LfoShape::from_argument(*arguments.get(SHAPE_ARG).unwrap())?;
+    let rate = *arguments.get(RATE_ARG).unwrap();
+    let phase = channel as f64 * arguments.get(STEREO_ARG).unwrap() / 360.0;
+    Ok(Lfo::new(shape, rate, phase, sample_rate, channel as u64 + 1))
+}
+
+fn ms_to_samples(ms: f64, sample_rate: u32) -> f64 {
+    ms / 1000.0 * sample_rate as f64
+}
+
+/// Runs every channel, followed by `tail` samples of silence, through a delay line read at a delay (in samples)
+/// that follows the LFO. `delay` gets the sample index and the LFO mapped to 0..1, and `mix` gives the (dry, wet) gains
+/// of the input and the delayed signal at a sample. The delayed signal is also fed back into the line.
+fn process_modulated_delay<F: Fn(usize, f64) -> f64>(buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation,
+    max_delay: f64, feedback: impl Fn(usize) -> f64, mix: impl Fn(usize) -> (f64, f64), delay: F) -> Result<(), String> {
+    let sample_rate = buffer.spec.sample_rate;
+    let rate_at = argument_values(RATE_ARG, arguments, automation);
+    let tail = max_delay.ceil() as usize + 2;
+
+    for (index, channel) in buffer.channels.iter_mut().enumerate() {
+        let mut lfo = channel_lfo(arguments, index, sample_rate)?;
+        let mut line = DelayLine::new(tail);
+        let mut delayed = 0.0;
+        channel.resize(channel.len() + tail, 0.0);
+
+        for (n, sample) in channel.iter_mut().enumerate() {
+            lfo.set_rate(rate_at(n), sample_rate);
+            line.push(*sample + feedback(n) * delayed);
+            delayed = line.read(delay(n, (lfo.next_value() + 1.0) / 2.0));
+            let (dry, wet) = mix(n);
+            *sample = dry * *sample + wet * delayed;
+        }
+    }
+
+    Ok(())
+}
+
+pub struct Tremolo;
+
+impl Tremolo {
+    const NAME: &'static str = "tremolo";
+}
+
+impl AudioEffect for Tremolo {
+    fn get_name(&self) -> String { Tremolo::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_lfo(arguments)?;
+        verify_range(&DEPTH_ARG.to_string(), 0.0, 1.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let depth_at = argument_values(DEPTH_ARG, arguments, automation);
+        let rate_at = argument_values(RATE_ARG, arguments, automation);
+        let sample_rate = buffer.spec.sample_rate;
+
+        // The gain swings between 1 - depth and 1
+        for (index, channel) in buffer.channels.iter_mut().enumerate() {
+            let mut lfo = channel_lfo(arguments, index, sample_rate)?;
+            for (n, sample) in channel.iter_mut().enumerate() {
+                lfo.set_rate(rate_at(n), sample_rate);
+                *sample *= 1.0 - depth_at(n) * (1.0 - lfo.next_value()) / 2.0;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == SHAPE_ARG).then_some(LfoShape::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, RATE_ARG | DEPTH_ARG)
+    }
+}
+
+pub struct Vibrato;
+
+impl Vibrato {
+    const NAME: &'static str = "vibrato";
+}
+
+impl AudioEffect for Vibrato {
+    fn get_name(&self) -> String { Vibrato::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_lfo(arguments)?;
+        verify_range(&DEPTH_ARG.to_string(), 0.0, MAX_DEPTH_MS, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let depth_at = argument_values(DEPTH_ARG, arguments, automation);
+        let max_depth = ms_to_samples(argument_max(DEPTH_ARG, arguments, automation), sample_rate);
+
+        // Only the delayed signal is heard, its changing delay bends the pitch
+        process_modulated_delay(buffer, arguments, automation, max_depth + 1.0, |_| 0.0, |_| (0.0, 1.0),
+            |n, lfo| 1.0 + ms_to_samples(depth_at(n), sample_rate) * lfo)?;
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == SHAPE_ARG).then_some(LfoShape::KEYWORDS)
+    }
+
+    fn get_latency(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> usize {
+        (1.0 + ms_to_samples(*arguments.get(DEPTH_ARG).unwrap(), sample_rate) / 2.0).round() as usize
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, RATE_ARG | DEPTH_ARG)
+    }
+}
+
+pub struct Chorus;
+
+impl Chorus {
+    const NAME: &'static str = "chorus";
+    const DELAY_ARG: &'static str = "delay";
+}
+
+impl AudioEffect for Chorus {
+    fn get_name(&self) -> String { Chorus::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_lfo(arguments)?;
+        verify_range(&DEPTH_ARG.to_string(), 0.0, MAX_DEPTH_MS, arguments)?;
+        verify_range(&Chorus::DELAY_ARG.to_string(), 0.0, MAX_DEPTH_MS, arguments)?;
+        verify_min(&WET_ARG.to_string(), 0.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let depth_at = argument_values(DEPTH_ARG, arguments, automation);
+        let delay_at = argument_values(Chorus::DELAY_ARG, arguments, automation);
+        let wet_at = argument_values(WET_ARG, arguments, automation);
+        let longest = argument_max(Chorus::DELAY_ARG, arguments, automation) + argument_max(DEPTH_ARG, arguments, automation);
+
+        // The delay sweeps from `delay` to `delay + depth`
+        process_modulated_delay(buffer, arguments, automation, ms_to_samples(longest, sample_rate) + 1.0, |_| 0.0, |n| (1.0, wet_at(n)),
+            |n, lfo| 1.0 + ms_to_samples(delay_at(n) + depth_at(n) * lfo, sample_rate))?;
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == SHAPE_ARG).then_some(LfoShape::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, RATE_ARG | DEPTH_ARG | Chorus::DELAY_ARG | WET_ARG)
+    }
+}
+
+pub struct Flanger;
+
+impl Flanger {
+    const NAME: &'static str = "flanger";
+}
+
+impl AudioEffect for Flanger {
+    fn get_name(&self) -> String { Flanger::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_lfo(arguments)?;
+        verify_range(&DEPTH_ARG.to_string(), 0.0, MAX_DEPTH_MS, arguments)?;
+        verify_range(&FEEDBACK_ARG.to_string(), -MAX_FEEDBACK, MAX_FEEDBACK, arguments)?;
+        verify_min(&WET_ARG.to_string(), 0.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let depth_at = argument_values(DEPTH_ARG, arguments, automation);
+        let feedback_at = argument_values(FEEDBACK_ARG, arguments, automation);
+        let wet_at = argument_values(WET_ARG, arguments, automation);
+        let max_depth = ms_to_samples(argument_max(DEPTH_ARG, arguments, automation), sample_rate);
+
+        // Short delays sweeping down to a single sample move a comb filter through the spectrum
+        process_modulated_delay(buffer, arguments, automation, max_depth + 1.0, feedback_at, |n| (1.0, wet_at(n)),
+            |n, lfo| 1.0 + ms_to_samples(depth_at(n), sample_rate) * lfo)?;
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == SHAPE_ARG).then_some(LfoShape::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, RATE_ARG | DEPTH_ARG | FEEDBACK_ARG | WET_ARG)
+    }
+}
+
+pub struct Phaser;
+
+impl Phaser {
+    const NAME: &'static str = "phaser";
+    const FREQ_ARG: &'static str = "freq";
+    const STAGES_ARG: &'static str = "stages";
+    const MAX_STAGES: f64 = 24.0;
+    /// Octaves the sweep can cover above `freq`
+    const MAX_DEPTH: f64 = 8.0;
+}
+
+impl AudioEffect for Phaser {
+    fn get_name(&self) -> String { Phaser::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        validate_lfo(arguments)?;
+        verify_range(&DEPTH_ARG.to_string(), 0.0, Phaser::MAX_DEPTH, arguments)?;
+        verify_range(&Phaser::FREQ_ARG.to_string(), 1.0, 20000.0, arguments)?;
+        verify_range(&FEEDBACK_ARG.to_string(), -MAX_FEEDBACK, MAX_FEEDBACK, arguments)?;
+        verify_min(&WET_ARG.to_string(), 0.0, arguments)?;
+
+        let stages = verify_range(&Phaser::STAGES_ARG.to_string(), 2.0, Phaser::MAX_STAGES, arguments)?;
+        if stages.fract() != 0.0 || !(stages as usize).is_multiple_of(2) {
+            return Err(format!("Argument '{}' must be an even number", Phaser::STAGES_ARG));
+        }
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate as f64;
+        let freq_at = argument_values(Phaser::FREQ_ARG, arguments, automation);
+        let depth_at = argument_values(DEPTH_ARG, arguments, automation);
+        let rate_at = argument_values(RATE_ARG, arguments, automation);
+        let stages = *arguments.get(Phaser::STAGES_ARG).unwrap() as usize;
+        let feedback_at = argument_values(FEEDBACK_ARG, arguments, automation);
+        let wet_at = argument_values(WET_ARG, arguments, automation);
+
+        for (index, channel) in buffer.channels.iter_mut().enumerate() {
+            let mut lfo = channel_lfo(arguments, index, buffer.spec.sample_rate)?;
+            let mut states = vec![0.0; stages];
+            let mut last = 0.0;
+
+            for (n, sample) in channel.iter_mut().enumerate() {
+                lfo.set_rate(rate_at(n), buffer.spec.sample_rate);
+                let (feedback, wet) = (feedback_at(n), wet_at(n));
+                // First order all-passes share a break frequency that sweeps `depth` octaves up from `freq`
+                let sweep = freq_at(n) * 2.0_f64.powf(depth_at(n) * (lfo.next_value() + 1.0) / 2.0);
+                let t = (f64::consts::PI * sweep.min(0.45 * sample_rate) / sample_rate).tan();
+                let coefficient = (t - 1.0) / (t + 1.0);
+
+                let mut value = *sample + feedback * last;
+                for state in states.iter_mut() {
+                    let output = coefficient * value + *state;
+                    *state = value - coefficient * output;
+                    value = output;
+                }
+                last = value;
+                *sample += wet * value;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == SHAPE_ARG).then_some(LfoShape::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, RATE_ARG | DEPTH_ARG | Phaser::FREQ_ARG | FEEDBACK_ARG | WET_ARG)
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{biquad::apply_automated_biquads, effect_modules::eq::BandShape, parse_utils::{arg_exists, verify_min, verify_range}, types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients}};
+
+pub struct Parametric;
+
+impl Parametric {
+    const NAME: &'static str = "parametric";
+    const TYPE_ARG: &'static str = "type";
+    const FREQ_ARG: &'static str = "freq";
+    const DB_ARG: &'static str = "db";
+    const Q_ARG: &'static str = "q";
+
+    /// Band numbers, taken from the `freqN` arguments
+    fn bands(arguments: &HashMap<String, f64>) -> BTreeSet<u32> {
+        arguments.keys()
+            .filter_map(|key| key.strip_prefix(Parametric::FREQ_ARG)?.parse().ok())
+            .collect()
+    }
+
+    fn band_argument(name: &str, band: u32) -> String {
+        format!("{name}{band}")
+    }
+
+    /// Every band with its shape, which is a keyword and resolved once for every design
+    fn band_shapes(arguments: &HashMap<String, f64>) -> Result<Vec<(u32, BandShape)>, String> {
+        Parametric::bands(arguments).into_iter()
+            .map(|band| Ok((band, BandShape::from_argument(arg_exists(&Parametric::band_argument(Parametric::TYPE_ARG, band), arguments)?)?)))
+            .collect()
+    }
+
+    fn band_coefficients(arguments: &HashMap<String, f64>, band: u32, shape: BandShape, sample_rate: u32) -> BiquadCoefficients {
+        let f0 = *arguments.get(&Parametric::band_argument(Parametric::FREQ_ARG, band)).unwrap();
+        let q = *arguments.get(&Parametric::band_argument(Parametric::Q_ARG, band)).unwrap();
+        let db_gain = arguments.get(&Parametric::band_argument(Parametric::DB_ARG, band)).copied().unwrap_or(0.0);
+
+        shape.coefficients(f0, db_gain, q, sample_rate)
+    }
+
+    fn coefficients(shapes: &[(u32, BandShape)], arguments: &HashMap<String, f64>, sample_rate: u32) -> Vec<BiquadCoefficients> {
+        shapes.iter()
+            .map(|(band, shape)| Parametric::band_coefficients(arguments, *band, *shape, sample_rate))
+            .collect()
+    }
+}
+
+impl AudioEffect for Parametric {
+
+    fn get_name(&self) -> String {
+        Parametric::NAME.to_string()
+    }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        let bands = Parametric::bands(arguments);
+        if bands.is_empty() {
+            return Err(format!("At least one band is required (arguments '{0}1', '{1}1', '{2}1' ...)", Parametric::TYPE_ARG, Parametric::FREQ_ARG, Parametric::Q_ARG));
+        }
+
+        for band in bands.iter() {
+            let shape = BandShape::from_argument(arg_exists(&Parametric::band_argument(Parametric::TYPE_ARG, *band), arguments)?)?;
+            verify_range(&Parametric::band_argument(Parametric::FREQ_ARG, *band), 1.0, 20000.0, arguments)?;
+            verify_min(&Parametric::band_argument(Parametric::Q_ARG, *band), 0.01, arguments)?;
+
+            if shape.has_gain() {
+                arg_exists(&Parametric::band_argument(Parametric::DB_ARG, *band), arguments)?;
+            }
+        }
+
+        // Catch typos and arguments of bands that have no frequency
+        for key in arguments.keys() {
+            let band = [Parametric::TYPE_ARG, Parametric::FREQ_ARG, Parametric::DB_ARG, Parametric::Q_ARG].iter()
+                .find_map(|name| key.strip_prefix(name)?.parse::<u32>().ok());
+            match band {
+                Some(band) if bands.contains(&band) => {},
+                Some(band) => return Err(format!("Band {band} is missing argument '{}'", Parametric::band_argument(Parametric::FREQ_ARG, band))),
+                None => return Err(format!("Unknown argument '{key}'")),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let sample_rate = buffer.spec.sample_rate;
+        let shapes = Parametric::band_shapes(arguments)?;
+        apply_automated_biquads(buffer, arguments, automation, |values| Parametric::coefficients(&shapes, values, sample_rate));
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, arguments: &HashMap<String, f64>, sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Parametric::band_shapes(arguments).ok().map(|shapes| Parametric::coefficients(&shapes, arguments, sample_rate))
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        let band = argument.strip_prefix(Parametric::TYPE_ARG)?;
+        band.parse::<u32>().is_ok().then_some(BandShape::KEYWORDS)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        [Parametric::FREQ_ARG, Parametric::DB_ARG, Parametric::Q_ARG].iter()
+            .any(|name| argument.strip_prefix(name).is_some_and(|band| band.parse::<u32>().is_ok()))
+    }
+}
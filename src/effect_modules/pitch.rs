@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::{parse_utils::verify_range, types::{AudioBuffer, AudioEffect}, vocoder};
+
+const METHOD_ARG: &str = "method";
+/// The phase vocoder suits music, WSOLA keeps the waveform intact and suits speech
+const METHODS: &[&str] = &["vocoder", "wsola"];
+const WSOLA: f64 = 1.0;
+
+/// Stretches every channel to `ratio` times its length with the chosen method
+fn stretch(buffer: &AudioBuffer, ratio: f64, method: f64, formant_shift: Option<f64>) -> Vec<Vec<f64>> {
+    let sample_rate = buffer.spec.sample_rate;
+    match method == WSOLA {
+        true => vocoder::wsola(&buffer.channels, ratio, sample_rate),
+        false => buffer.channels.iter().map(|channel| vocoder::phase_vocoder(channel, ratio, formant_shift, sample_rate)).collect(),
+    }
+}
+
+pub struct TimeStretch;
+
+impl TimeStretch {
+    const NAME: &'static str = "timestretch";
+    const RATIO_ARG: &'static str = "ratio";
+    const MIN_RATIO: f64 = 0.25;
+    const MAX_RATIO: f64 = 4.0;
+}
+
+impl AudioEffect for TimeStretch {
+    fn get_name(&self) -> String { TimeStretch::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&TimeStretch::RATIO_ARG.to_string(), TimeStretch::MIN_RATIO, TimeStretch::MAX_RATIO, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let ratio = *arguments.get(TimeStretch::RATIO_ARG).unwrap();
+        let method = arguments.get(METHOD_ARG).copied().unwrap_or(0.0);
+
+        buffer.channels = stretch(buffer, ratio, method, None);
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        (argument == METHOD_ARG).then_some(METHODS)
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
+
+pub struct PitchShift;
+
+impl PitchShift {
+    const NAME: &'static str = "pitchshift";
+    const SEMITONES_ARG: &'static str = "semitones";
+    const FORMANTS_ARG: &'static str = "formants";
+    const FORMANTS: &'static [&'static str] = &["shift", "keep"];
+    const MAX_SEMITONES: f64 = 24.0;
+}
+
+impl AudioEffect for PitchShift {
+    fn get_name(&self) -> String { PitchShift::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&PitchShift::SEMITONES_ARG.to_string(), -PitchShift::MAX_SEMITONES, PitchShift::MAX_SEMITONES, arguments)?;
+
+        if arguments.get(PitchShift::FORMANTS_ARG) == Some(&1.0) && arguments.get(METHOD_ARG) == Some(&WSOLA) {
+            return Err(format!("Formants can only be kept with '{METHOD_ARG}={}'", METHODS[0]));
+        }
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let factor = 2.0_f64.powf(arguments.get(PitchShift::SEMITONES_ARG).unwrap() / 12.0);
+        let method = arguments.get(METHOD_ARG).copied().unwrap_or(0.0);
+        let keep_formants = arguments.get(PitchShift::FORMANTS_ARG) == Some(&1.0);
+        let length = buffer.channels[0].len();
+
+        // Stretching by the pitch factor and reading the result back at that speed restores the length
+        let stretched = stretch(buffer, factor, method, keep_formants.then_some(factor));
+        buffer.channels = stretched.iter().map(|channel| vocoder::resample(channel, factor, length)).collect();
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        match argument {
+            METHOD_ARG => Some(METHODS),
+            PitchShift::FORMANTS_ARG => Some(PitchShift::FORMANTS),
+            _ => None,
+        }
+    }
+}
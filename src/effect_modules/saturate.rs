@@ -0,0 +1,156 @@
+use core::f64;
+use std::collections::HashMap;
+
+use crate::{audio_utils::quantize, automation::argument_values, oversampling, parse_utils::{arg_exists, keyword_index, keyword_variant, verify_range}, types::{AudioBuffer, AudioEffect, Automation}};
+
+/// Cutoff of the high-pass that removes the offset asymmetric curves and bias leave behind
+const DC_BLOCK_FREQ: f64 = 10.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Curve {
+    Tanh,
+    Arctan,
+    Cubic,
+    HardClip,
+    Foldback,
+    Tube,
+    Bitcrush,
+}
+
+impl Curve {
+    const KEYWORDS: &'static [&'static str] = &["tanh", "arctan", "cubic", "hardclip", "foldback", "tube", "bitcrush"];
+    const VARIANTS: [Curve; 7] = [Curve::Tanh, Curve::Arctan, Curve::Cubic, Curve::HardClip, Curve::Foldback, Curve::Tube, Curve::Bitcrush];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<Curve, String> {
+        keyword_variant(value, Curve::KEYWORDS, &Curve::VARIANTS)
+    }
+
+    /// All curves have unity slope around zero (cubic has 1.5) and stay within [-1, 1] for any input
+    fn shape(&self, x: f64, levels: f64) -> f64 {
+        match self {
+            Curve::Tanh => x.tanh(),
+            Curve::Arctan => x.atan() * f64::consts::FRAC_2_PI,
+            Curve::Cubic => match x.abs() < 1.0 {
+                true => 1.5 * x - 0.5 * x * x * x,
+                false => x.signum(),
+            },
+            Curve::HardClip => x.clamp(-1.0, 1.0),
+            // Everything past full scale is mirrored back into range, as often as needed
+            Curve::Foldback => {
+                let t = (x + 1.0).rem_euclid(4.0);
+                if t < 2.0 { t - 1.0 } else { 3.0 - t }
+            },
+            // The negative half clips twice as early and harder, which adds even harmonics
+            Curve::Tube => match x >= 0.0 {
+                true => 1.0 - (-x).exp(),
+                false => -0.5 * (1.0 - (2.0 * x).exp()),
+            },
+            Curve::Bitcrush => quantize(x, levels),
+        }
+    }
+}
+
+pub struct Saturate;
+
+impl Saturate {
+    const NAME: &'static str = "saturate";
+    const CURVE_ARG: &'static str = "curve";
+    const DRIVE_ARG: &'static str = "drive";
+    const OUT_ARG: &'static str = "out";
+    const BIAS_ARG: &'static str = "bias";
+    const BITS_ARG: &'static str = "bits";
+    const DC_BLOCK_ARG: &'static str = "dcblock";
+    const OVERSAMPLE_ARG: &'static str = "oversample";
+    const SWITCH: &'static [&'static str] = &["off", "on"];
+}
+
+impl AudioEffect for Saturate {
+    fn get_name(&self) -> String { Saturate::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        let curve = Curve::from_argument(arg_exists(&Saturate::CURVE_ARG.to_string(), arguments)?)?;
+        verify_range(&Saturate::DRIVE_ARG.to_string(), -24.0, 60.0, arguments)?;
+
+        if arguments.contains_key(Saturate::OUT_ARG) {
+            verify_range(&Saturate::OUT_ARG.to_string(), -60.0, 24.0, arguments)?;
+        }
+        if arguments.contains_key(Saturate::BIAS_ARG) {
+            verify_range(&Saturate::BIAS_ARG.to_string(), -1.0, 1.0, arguments)?;
+        }
+
+        if curve == Curve::Bitcrush {
+            let bits = verify_range(&Saturate::BITS_ARG.to_string(), 1.0, 24.0, arguments)?;
+            if bits.fract() != 0.0 {
+                return Err(format!("Argument '{}' must be a whole number", Saturate::BITS_ARG));
+            }
+        }
+
+        if let Some(factor) = arguments.get(Saturate::OVERSAMPLE_ARG)
+            && !oversampling::FACTORS.iter().any(|f| *f as f64 == *factor) {
+            return Err(format!("Argument '{}' must be one of {}", Saturate::OVERSAMPLE_ARG,
+                oversampling::FACTORS.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", ")));
+        }
+
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let curve = Curve::from_argument(*arguments.get(Saturate::CURVE_ARG).unwrap())?;
+        let dc_block = match arguments.get(Saturate::DC_BLOCK_ARG) {
+            Some(switch) => Saturate::SWITCH[keyword_index(*switch, Saturate::SWITCH)?] == "on",
+            None => true,
+        };
+        let factor = *arguments.get(Saturate::OVERSAMPLE_ARG).unwrap_or(&1.0) as usize;
+
+        let mut arguments = arguments.clone();
+        for (argument, default) in [(Saturate::OUT_ARG, 0.0), (Saturate::BIAS_ARG, 0.0), (Saturate::BITS_ARG, 1.0)] {
+            arguments.entry(argument.to_string()).or_insert(default);
+        }
+        let drive_at = argument_values(Saturate::DRIVE_ARG, &arguments, automation);
+        let out_at = argument_values(Saturate::OUT_ARG, &arguments, automation);
+        let bias_at = argument_values(Saturate::BIAS_ARG, &arguments, automation);
+        let bits_at = argument_values(Saturate::BITS_ARG, &arguments, automation);
+        let pole = 1.0 - f64::consts::TAU * DC_BLOCK_FREQ / buffer.spec.sample_rate as f64;
+
+        for channel in buffer.channels.iter_mut() {
+            // Oversampled samples use the values of the sample they were made from
+            let mut index = 0;
+            oversampling::process(channel, factor, |x| {
+                let n = index / factor;
+                index += 1;
+                let (drive, bias, levels) = (10.0_f64.powf(drive_at(n) / 20.0), bias_at(n), 2.0_f64.powf(bits_at(n) - 1.0));
+                // The curve's output for the bias alone is subtracted, so silence stays silent
+                curve.shape(drive * x + bias, levels) - curve.shape(bias, levels)
+            });
+
+            let (mut last_input, mut last_output) = (0.0, 0.0);
+            for (n, sample) in channel.iter_mut().enumerate() {
+                if dc_block {
+                    last_output = *sample - last_input + pole * last_output;
+                    last_input = *sample;
+                    *sample = last_output;
+                }
+                *sample *= 10.0_f64.powf(out_at(n) / 20.0);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        match argument {
+            Saturate::CURVE_ARG => Some(Curve::KEYWORDS),
+            Saturate::DC_BLOCK_ARG => Some(Saturate::SWITCH),
+            _ => None,
+        }
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        matches!(argument, Saturate::DRIVE_ARG | Saturate::OUT_ARG | Saturate::BIAS_ARG | Saturate::BITS_ARG)
+    }
+}
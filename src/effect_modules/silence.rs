@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use crate::{audio_utils::find_sound, parse_utils::{time_in_samples, verify_max, verify_time}, types::{AudioBuffer, AudioEffect}};
+
+pub struct TrimSilence;
+
+impl TrimSilence {
+    const NAME: &'static str = "trimsilence";
+    const DB_ARG: &'static str = "db";
+    const MIN_ARG: &'static str = "min";
+}
+
+impl AudioEffect for TrimSilence {
+    fn get_name(&self) -> String { TrimSilence::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_max(&TrimSilence::DB_ARG.to_string(), 0.0, arguments)?;
+        verify_time(TrimSilence::MIN_ARG, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        let threshold = 10.0_f64.powf(arguments.get(TrimSilence::DB_ARG).unwrap() / 20.0);
+        let min_silence = time_in_samples(TrimSilence::MIN_ARG, arguments, buffer.spec.sample_rate);
+        let length = buffer.channels[0].len();
+
+        let regions = find_sound(buffer, threshold, 0);
+        let (Some(first), Some(last)) = (regions.first(), regions.last()) else {
+            return Err("The audio is silent from start to end".to_string());
+        };
+
+        // Silence shorter than the minimum is kept
+        let start = if first.start >= min_silence { first.start } else { 0 };
+        let end = if length - last.end >= min_silence { last.end } else { length };
+
+        for channel in buffer.channels.iter_mut() {
+            channel.truncate(end);
+            channel.drain(..start);
+        }
+
+        let sample_rate = buffer.spec.sample_rate as f64;
+        Ok(Some(format!("removed {:.2}s before, {:.2}s after", start as f64 / sample_rate, (length - end) as f64 / sample_rate)))
+    }
+
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[TrimSilence::MIN_ARG]
+    }
+
+    fn changes_duration(&self) -> bool {
+        true
+    }
+}
@@ -29,4 +29,8 @@ impl AudioEffect for Softclip {
         
         Ok(None)
     }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == Softclip::DB_ARG
+    }
 }
\ No newline at end of file
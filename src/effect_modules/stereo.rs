@@ -0,0 +1,295 @@
+use core::f64;
+use std::collections::HashMap;
+
+use crate::{automation::argument_values, biquad::BiquadCascade, effect_modules::eq::{AllPassEQ, BandPassEQ, PeakingEQ}, parse_utils::{arg_exists, keyword_variant, verify_min, verify_range}, types::{AudioBuffer, AudioEffect, Automation, BiquadCoefficients}};
+
+const POS_ARG: &str = "pos";
+const AMOUNT_ARG: &str = "amount";
+
+fn verify_stereo(buffer: &AudioBuffer) -> Result<(), String> {
+    if buffer.channels.len() != 2 {
+        return Err(format!("Needs a stereo signal, the signal has {} channel(s)", buffer.channels.len()));
+    }
+    Ok(())
+}
+
+/// Applies a 2x2 matrix to every stereo frame: (L, R) -> (ll * L + rl * R, lr * L + rr * R)
+fn apply_matrix(buffer: &mut AudioBuffer, ll: f64, rl: f64, lr: f64, rr: f64) {
+    let (left, right) = buffer.channels.split_at_mut(1);
+    for (l, r) in left[0].iter_mut().zip(right[0].iter_mut()) {
+        let (x, y) = (*l, *r);
+        *l = ll * x + rl * y;
+        *r = lr * x + rr * y;
+    }
+}
+
+pub struct Width;
+
+impl Width {
+    const NAME: &'static str = "width";
+}
+
+impl AudioEffect for Width {
+    fn get_name(&self) -> String { Width::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_min(&AMOUNT_ARG.to_string(), 0.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        verify_stereo(buffer)?;
+        let amount = *arguments.get(AMOUNT_ARG).unwrap();
+
+        // Mid stays, side is scaled by the amount
+        let (mid, side) = (0.5, 0.5 * amount);
+        apply_matrix(buffer, mid + side, mid - side, mid - side, mid + side);
+
+        Ok(None)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == AMOUNT_ARG
+    }
+}
+
+/// Pan laws, the level of a source in the center: -3 dB for constant power, -6 dB for linear
+#[derive(Clone, Copy)]
+enum PanLaw {
+    ConstantPower,
+    Linear,
+}
+
+impl PanLaw {
+    const KEYWORDS: &'static [&'static str] = &["constantpower", "linear"];
+    const VARIANTS: [PanLaw; 2] = [PanLaw::ConstantPower, PanLaw::Linear];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<PanLaw, String> {
+        keyword_variant(value, PanLaw::KEYWORDS, &PanLaw::VARIANTS)
+    }
+
+    /// Left and right gains of a source at `position`, a source panned hard keeps its level
+    fn gains(&self, position: f64) -> (f64, f64) {
+        match self {
+            PanLaw::ConstantPower => {
+                let theta = (position + 1.0) * f64::consts::FRAC_PI_4;
+                (theta.cos(), theta.sin())
+            },
+            PanLaw::Linear => ((1.0 - position) / 2.0, (1.0 + position) / 2.0),
+        }
+    }
+}
+
+/// A stereo pan moves the image as a whole, a mono pan sums both channels first and places the sum
+#[derive(Clone, Copy)]
+enum PanMode {
+    Stereo,
+    Mono,
+}
+
+impl PanMode {
+    const KEYWORDS: &'static [&'static str] = &["stereo", "mono"];
+    const VARIANTS: [PanMode; 2] = [PanMode::Stereo, PanMode::Mono];
+
+    /// Keyword arguments hold the index of their keyword
+    fn from_argument(value: f64) -> Result<PanMode, String> {
+        keyword_variant(value, PanMode::KEYWORDS, &PanMode::VARIANTS)
+    }
+}
+
+pub struct Pan;
+
+impl Pan {
+    const NAME: &'static str = "pan";
+    const LAW_ARG: &'static str = "law";
+    const MODE_ARG: &'static str = "mode";
+
+    fn mode(arguments: &HashMap<String, f64>) -> Result<PanMode, String> {
+        arguments.get(Pan::MODE_ARG).map_or(Ok(PanMode::Stereo), |value| PanMode::from_argument(*value))
+    }
+}
+
+impl AudioEffect for Pan {
+    fn get_name(&self) -> String { Pan::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&POS_ARG.to_string(), -1.0, 1.0, arguments)?;
+        PanLaw::from_argument(arg_exists(&Pan::LAW_ARG.to_string(), arguments)?)?;
+        Pan::mode(arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        verify_stereo(buffer)?;
+        let position = *arguments.get(POS_ARG).unwrap();
+        let law = PanLaw::from_argument(*arguments.get(Pan::LAW_ARG).unwrap())?;
+
+        match Pan::mode(arguments)? {
+            // The channel on the far side travels over to the other one, which stays where it is
+            PanMode::Stereo if position >= 0.0 => {
+                let (left, right) = law.gains(2.0 * position - 1.0);
+                apply_matrix(buffer, left, 0.0, right, 1.0);
+            },
+            PanMode::Stereo => {
+                let (left, right) = law.gains(2.0 * position + 1.0);
+                apply_matrix(buffer, 1.0, left, 0.0, right);
+            },
+            PanMode::Mono => {
+                let (left, right) = law.gains(position);
+                apply_matrix(buffer, left / 2.0, left / 2.0, right / 2.0, right / 2.0);
+            },
+        }
+
+        Ok(None)
+    }
+
+    fn get_keywords(&self, argument: &str) -> Option<&'static [&'static str]> {
+        match argument {
+            Pan::LAW_ARG => Some(PanLaw::KEYWORDS),
+            Pan::MODE_ARG => Some(PanMode::KEYWORDS),
+            _ => None,
+        }
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == POS_ARG
+    }
+}
+
+pub struct Balance;
+
+impl Balance {
+    const NAME: &'static str = "balance";
+}
+
+impl AudioEffect for Balance {
+    fn get_name(&self) -> String { Balance::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&POS_ARG.to_string(), -1.0, 1.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        verify_stereo(buffer)?;
+        let position = *arguments.get(POS_ARG).unwrap();
+
+        // Only the opposite channel is turned down, so the image is kept
+        let left = (1.0 - position).min(1.0);
+        let right = (1.0 + position).min(1.0);
+        apply_matrix(buffer, left, 0.0, 0.0, right);
+
+        Ok(None)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == POS_ARG
+    }
+}
+
+pub struct Swap;
+
+impl Swap {
+    const NAME: &'static str = "swap";
+}
+
+impl AudioEffect for Swap {
+    fn get_name(&self) -> String { Swap::NAME.to_string() }
+
+    fn validate_arguments(&self, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        verify_stereo(buffer)?;
+        buffer.channels.swap(0, 1);
+
+        Ok(None)
+    }
+}
+
+pub struct Invert;
+
+impl Invert {
+    const NAME: &'static str = "invert";
+}
+
+impl AudioEffect for Invert {
+    fn get_name(&self) -> String { Invert::NAME.to_string() }
+
+    fn validate_arguments(&self, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, _arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        for channel in buffer.channels.iter_mut() {
+            for sample in channel.iter_mut() {
+                *sample = -*sample;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn get_biquads(&self, _arguments: &HashMap<String, f64>, _sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        Some(vec![BiquadCoefficients { b0: -1.0, b1: 0.0, b2: 0.0, a0: 1.0, a1: 0.0, a2: 0.0 }])
+    }
+}
+
+pub struct MonoToStereo;
+
+impl MonoToStereo {
+    const NAME: &'static str = "mono-to-stereo";
+    /// Centers of the all-pass sections that make up the decorrelation filter
+    const ALLPASS_FREQS: [f64; 6] = [120.0, 310.0, 780.0, 1900.0, 4700.0, 11000.0];
+    const ALLPASS_Q: f64 = 0.6;
+}
+
+impl AudioEffect for MonoToStereo {
+    fn get_name(&self) -> String { MonoToStereo::NAME.to_string() }
+
+    fn validate_arguments(&self, arguments: &HashMap<String, f64>, _tail_length: &Option<f64>) -> Result<(), String> {
+        verify_range(&AMOUNT_ARG.to_string(), 0.0, 1.0, arguments)?;
+        Ok(())
+    }
+
+    fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        self.apply_automated(buffer, arguments, &Automation::new(), tail_length)
+    }
+
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, _tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        if buffer.channels.len() != 1 {
+            return Err(format!("Needs a mono signal, the signal has {} channel(s)", buffer.channels.len()));
+        }
+        let amount_at = argument_values(AMOUNT_ARG, arguments, automation);
+        let sample_rate = buffer.spec.sample_rate;
+
+        let sections: Vec<BiquadCoefficients> = MonoToStereo::ALLPASS_FREQS.iter()
+            .filter(|freq| **freq < sample_rate as f64 / 2.0)
+            .map(|freq| {
+                let allpass_arguments = HashMap::from([
+                    (PeakingEQ::FREQ_ARG.to_string(), *freq),
+                    (BandPassEQ::Q_ARG.to_string(), MonoToStereo::ALLPASS_Q),
+                ]);
+                AllPassEQ::coefficients(&allpass_arguments, sample_rate)
+            })
+            .collect();
+
+        // The all-passed copy becomes the side signal, so the mono sum is left untouched
+        let mid = buffer.channels.remove(0);
+        let mut side = mid.clone();
+        BiquadCascade::new(&sections, 1).process_block(0, &mut side);
+
+        let left = mid.iter().zip(side.iter()).enumerate().map(|(n, (m, s))| m + amount_at(n) * s).collect();
+        let right = mid.iter().zip(side.iter()).enumerate().map(|(n, (m, s))| m - amount_at(n) * s).collect();
+        buffer.channels = vec![left, right];
+        buffer.spec.channels = 2;
+
+        Ok(None)
+    }
+
+    fn is_automatable(&self, argument: &str) -> bool {
+        argument == AMOUNT_ARG
+    }
+}
@@ -1,4 +1,4 @@
-use std::{i16, i32, path::PathBuf};
+use std::path::PathBuf;
 
 use hound::{WavWriter, SampleFormat};
 use crate::types::{AudioBuffer, I24_MAX};
@@ -0,0 +1,70 @@
+use core::f64;
+
+use crate::{parse_utils::keyword_variant, random::Xorshift};
+
+/// Waveforms of the low frequency oscillator
+#[derive(Clone, Copy)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Square,
+    /// Glides to a new random value every cycle
+    Random,
+}
+
+impl LfoShape {
+    pub const KEYWORDS: &'static [&'static str] = &["sine", "triangle", "square", "random"];
+    const VARIANTS: [LfoShape; 4] = [LfoShape::Sine, LfoShape::Triangle, LfoShape::Square, LfoShape::Random];
+
+    /// Keyword arguments hold the index of their keyword
+    pub fn from_argument(value: f64) -> Result<LfoShape, String> {
+        keyword_variant(value, LfoShape::KEYWORDS, &LfoShape::VARIANTS)
+    }
+}
+
+/// Low frequency oscillator between -1 and 1
+pub struct Lfo {
+    shape: LfoShape,
+    /// Position in the current cycle, from 0 to 1
+    phase: f64,
+    increment: f64,
+    random: Xorshift,
+    from: f64,
+    to: f64,
+}
+
+impl Lfo {
+    /// `phase` is where the oscillator starts in its cycle (0 to 1), so channels can be offset from each other.
+    /// Random oscillators with different seeds move independently.
+    pub fn new(shape: LfoShape, rate: f64, phase: f64, sample_rate: u32, seed: u64) -> Lfo {
+        let mut lfo = Lfo { shape, phase: phase.rem_euclid(1.0), increment: rate / sample_rate as f64, random: Xorshift::new(seed), from: 0.0, to: 0.0 };
+        lfo.from = lfo.random.next_value();
+        lfo.to = lfo.random.next_value();
+        lfo
+    }
+
+    /// Changes the speed without a jump in the output
+    pub fn set_rate(&mut self, rate: f64, sample_rate: u32) {
+        self.increment = rate / sample_rate as f64;
+    }
+
+    pub fn next_value(&mut self) -> f64 {
+        let phase = self.phase;
+        let value = match self.shape {
+            LfoShape::Sine => (2.0 * f64::consts::PI * phase).sin(),
+            // Shifted by a quarter cycle so it starts at 0 and rises, like the sine
+            LfoShape::Triangle => 1.0 - 4.0 * ((phase + 0.25).rem_euclid(1.0) - 0.5).abs(),
+            LfoShape::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            LfoShape::Random => self.from + (self.to - self.from) * phase,
+        };
+
+        self.phase += self.increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.from = self.to;
+            self.to = self.random.next_value();
+        }
+
+        value
+    }
+}
@@ -0,0 +1,220 @@
+use core::f64;
+
+use crate::{biquad::BiquadCascade, types::{AudioBuffer, BiquadCoefficients}};
+
+// ITU-R BS.1770-4 / EBU R128 measurement constants
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+const MOMENTARY_WINDOW_SECONDS: f64 = 0.4;
+const SHORT_TERM_WINDOW_SECONDS: f64 = 3.0;
+const BLOCK_STEP_SECONDS: f64 = 0.1;
+const TRUE_PEAK_HALF_TAPS: isize = 12;
+
+pub struct LoudnessStats {
+    pub integrated: f64,
+    pub range: f64,
+    pub true_peak: f64,
+    pub momentary_max: f64,
+    pub short_term_max: f64,
+}
+
+// K-weighting coefficients are derived for any sample rate (the spec only lists them for 48 kHz)
+fn k_weighting_shelf(fs: f64) -> BiquadCoefficients {
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+
+    let k = (f64::consts::PI * f0 / fs).tan();
+    let vh = 10.0_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+
+    BiquadCoefficients {
+        b0: vh + vb * k / q + k * k,
+        b1: 2.0 * (k * k - vh),
+        b2: vh - vb * k / q + k * k,
+        a0: 1.0 + k / q + k * k,
+        a1: 2.0 * (k * k - 1.0),
+        a2: 1.0 - k / q + k * k,
+    }
+}
+
+fn k_weighting_high_pass(fs: f64) -> BiquadCoefficients {
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+
+    let k = (f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+
+    BiquadCoefficients {
+        b0: a0,
+        b1: -2.0 * a0,
+        b2: a0,
+        a0,
+        a1: 2.0 * (k * k - 1.0),
+        a2: 1.0 - k / q + k * k,
+    }
+}
+
+/// Channel weighting from BS.1770: surrounds are boosted by ~1.5 dB, LFE is ignored
+fn channel_weight(index: usize, channel_count: usize) -> f64 {
+    match (channel_count, index) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) | (5, 3) | (5, 4) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn k_weighted(buffer: &AudioBuffer) -> Vec<Vec<f64>> {
+    let fs = buffer.spec.sample_rate as f64;
+    let mut filter = BiquadCascade::new(&[k_weighting_shelf(fs), k_weighting_high_pass(fs)], buffer.channels.len());
+
+    buffer.channels.iter().enumerate().map(|(index, channel)| {
+        let mut filtered = channel.clone();
+        filter.process_block(index, &mut filtered);
+        filtered
+    }).collect()
+}
+
+/// Weighted mean square power of each (overlapping) block of `window` seconds
+fn block_powers(weighted: &[Vec<f64>], sample_rate: u32, window: f64) -> Vec<f64> {
+    let window_samples = (window * sample_rate as f64).round() as usize;
+    let step_samples = (BLOCK_STEP_SECONDS * sample_rate as f64).round() as usize;
+    let length = weighted.first().map_or(0, |c| c.len());
+
+    if window_samples == 0 || length < window_samples {
+        return Vec::new();
+    }
+
+    let squared: Vec<Vec<f64>> = weighted.iter().map(|c| c.iter().map(|s| s * s).collect()).collect();
+    let block_count = (length - window_samples) / step_samples + 1;
+    let mut powers = Vec::with_capacity(block_count);
+
+    for block in 0..block_count {
+        let start = block * step_samples;
+        let mut power = 0.0;
+        for (index, channel) in squared.iter().enumerate() {
+            let weight = channel_weight(index, squared.len());
+            if weight == 0.0 {
+                continue;
+            }
+            let sum: f64 = channel[start..start + window_samples].iter().sum();
+            power += weight * sum / window_samples as f64;
+        }
+        powers.push(power);
+    }
+
+    powers
+}
+
+fn gated_integrated(powers: &[f64]) -> f64 {
+    let absolute_gated: Vec<f64> = powers.iter().copied()
+        .filter(|p| to_lufs(*p) > ABSOLUTE_GATE_LUFS)
+        .collect();
+
+    if absolute_gated.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+
+    let relative_gate = to_lufs(absolute_gated.iter().sum::<f64>() / absolute_gated.len() as f64) + INTEGRATED_RELATIVE_GATE_LU;
+    let relative_gated: Vec<f64> = absolute_gated.into_iter()
+        .filter(|p| to_lufs(*p) > relative_gate)
+        .collect();
+
+    if relative_gated.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+
+    to_lufs(relative_gated.iter().sum::<f64>() / relative_gated.len() as f64)
+}
+
+/// Loudness range as specified in EBU Tech 3342
+fn gated_range(powers: &[f64]) -> f64 {
+    let absolute_gated: Vec<f64> = powers.iter().copied()
+        .filter(|p| to_lufs(*p) > ABSOLUTE_GATE_LUFS)
+        .collect();
+
+    if absolute_gated.is_empty() {
+        return 0.0;
+    }
+
+    let relative_gate = to_lufs(absolute_gated.iter().sum::<f64>() / absolute_gated.len() as f64) + RANGE_RELATIVE_GATE_LU;
+    let mut levels: Vec<f64> = absolute_gated.into_iter()
+        .map(to_lufs)
+        .filter(|l| *l > relative_gate)
+        .collect();
+
+    if levels.is_empty() {
+        return 0.0;
+    }
+
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f64| levels[((levels.len() - 1) as f64 * p).round() as usize];
+    percentile(0.95) - percentile(0.10)
+}
+
+fn oversampling_factor(sample_rate: u32) -> usize {
+    match sample_rate {
+        0..96000 => 4,
+        96000..192000 => 2,
+        _ => 1,
+    }
+}
+
+/// Peak of the signal reconstructed with a windowed sinc interpolator (linear scale)
+pub fn true_peak(channels: &[Vec<f64>], sample_rate: u32) -> f64 {
+    let factor = oversampling_factor(sample_rate);
+
+    // One set of interpolation taps for each fractional phase between samples
+    let phases: Vec<Vec<f64>> = (1..factor).map(|phase| {
+        let offset = phase as f64 / factor as f64;
+        (-TRUE_PEAK_HALF_TAPS + 1..=TRUE_PEAK_HALF_TAPS).map(|k| {
+            let t = k as f64 - offset;
+            let sinc = (f64::consts::PI * t).sin() / (f64::consts::PI * t);
+            let window = 0.5 + 0.5 * (f64::consts::PI * t / TRUE_PEAK_HALF_TAPS as f64).cos();
+            sinc * window
+        }).collect()
+    }).collect();
+
+    let mut peak: f64 = 0.0;
+    for channel in channels.iter() {
+        let length = channel.len() as isize;
+        for (n, sample) in channel.iter().enumerate() {
+            peak = peak.max(sample.abs());
+
+            for taps in phases.iter() {
+                let mut value = 0.0;
+                for (tap, k) in taps.iter().zip(-TRUE_PEAK_HALF_TAPS + 1..=TRUE_PEAK_HALF_TAPS) {
+                    let index = n as isize + k;
+                    if index >= 0 && index < length {
+                        value += tap * channel[index as usize];
+                    }
+                }
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+
+    peak
+}
+
+pub fn measure(buffer: &AudioBuffer) -> LoudnessStats {
+    let weighted = k_weighted(buffer);
+    let sample_rate = buffer.spec.sample_rate;
+
+    let momentary = block_powers(&weighted, sample_rate, MOMENTARY_WINDOW_SECONDS);
+    let short_term = block_powers(&weighted, sample_rate, SHORT_TERM_WINDOW_SECONDS);
+    let loudest = |powers: &[f64]| powers.iter().copied().map(to_lufs).fold(f64::NEG_INFINITY, f64::max);
+
+    LoudnessStats {
+        integrated: gated_integrated(&momentary),
+        range: gated_range(&short_term),
+        true_peak: true_peak(&buffer.channels, sample_rate),
+        momentary_max: loudest(&momentary),
+        short_term_max: loudest(&short_term),
+    }
+}
@@ -4,21 +4,40 @@ mod types;
 mod audio_utils;
 mod parse_utils;
 mod effect_modules;
+mod biquad;
+mod lfo;
+mod random;
+mod delay_line;
+mod vocoder;
+mod oversampling;
+mod multiband;
+mod routing;
+mod channel_target;
+mod region;
+mod automation;
+mod loudness;
+mod commands;
+mod chain;
+mod spectrum;
+mod plot;
 
 use colored::Colorize;
 use std::{collections::HashMap, path::PathBuf, time::Duration};
-use clap::{CommandFactory, Parser, error::ErrorKind};
+use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
 use std::time::Instant;
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{audio_utils::get_buffer_duration, types::AudioEffect};
 
 #[derive(Parser)]
-#[command(name="fiis", version, about, long_about= None)]
+#[command(name="fiis", version, about, long_about= None, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// File path of the .wav file
-    #[arg()]
-    file_path: PathBuf,
+    #[arg(required = true)]
+    file_path: Option<PathBuf>,
 
     /// Where to output the processed file
     #[arg(short, long)]
@@ -36,6 +55,22 @@ struct Args {
     effects: Vec<String>,
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Report signal statistics of a .wav file
+    Analyze(commands::analyze::AnalyzeArgs),
+    /// Render waveform and spectrogram images of a .wav file, optionally before and after an effects chain
+    Render(commands::render::RenderArgs),
+    /// Compute the frequency and phase response of an effects chain
+    Response(commands::response::ResponseArgs),
+    /// Null test two .wav files and report what changed
+    Diff(commands::diff::DiffArgs),
+    /// Join .wav files end to end, optionally with crossfades
+    Concat(commands::concat::ConcatArgs),
+    /// Split a .wav file on silence or into pieces of equal length
+    Split(commands::split::SplitArgs),
+}
+
 fn error(message: String, kind: ErrorKind) {
     let mut cmd = Args::command();
     cmd.error(kind, message).exit();
@@ -58,9 +93,57 @@ fn main() {
     add_effect(effect_modules::eq::BandPassEQ, &mut effect_map);
     add_effect(effect_modules::eq::HShelfEQ, &mut effect_map);
     add_effect(effect_modules::eq::LShelfEQ, &mut effect_map);
+    add_effect(effect_modules::eq::NotchEQ, &mut effect_map);
+    add_effect(effect_modules::eq::AllPassEQ, &mut effect_map);
+    add_effect(effect_modules::eq::TiltEQ, &mut effect_map);
+    add_effect(effect_modules::parametric::Parametric, &mut effect_map);
+    add_effect(effect_modules::loudnorm::LoudNorm, &mut effect_map);
+    add_effect(effect_modules::filter::HighPass, &mut effect_map);
+    add_effect(effect_modules::filter::LowPass, &mut effect_map);
+    add_effect(effect_modules::stereo::Width, &mut effect_map);
+    add_effect(effect_modules::stereo::Pan, &mut effect_map);
+    add_effect(effect_modules::stereo::Balance, &mut effect_map);
+    add_effect(effect_modules::stereo::Swap, &mut effect_map);
+    add_effect(effect_modules::stereo::Invert, &mut effect_map);
+    add_effect(effect_modules::stereo::MonoToStereo, &mut effect_map);
+    add_effect(effect_modules::channels::Channels, &mut effect_map);
+    add_effect(effect_modules::edit::Trim, &mut effect_map);
+    add_effect(effect_modules::edit::Cut, &mut effect_map);
+    add_effect(effect_modules::edit::Pad, &mut effect_map);
+    add_effect(effect_modules::edit::Reverse, &mut effect_map);
+    add_effect(effect_modules::edit::Repeat, &mut effect_map);
+    add_effect(effect_modules::fade::FadeIn, &mut effect_map);
+    add_effect(effect_modules::fade::FadeOut, &mut effect_map);
+    add_effect(effect_modules::silence::TrimSilence, &mut effect_map);
+    add_effect(effect_modules::modulation::Tremolo, &mut effect_map);
+    add_effect(effect_modules::modulation::Vibrato, &mut effect_map);
+    add_effect(effect_modules::modulation::Chorus, &mut effect_map);
+    add_effect(effect_modules::modulation::Flanger, &mut effect_map);
+    add_effect(effect_modules::modulation::Phaser, &mut effect_map);
+    add_effect(effect_modules::pitch::TimeStretch, &mut effect_map);
+    add_effect(effect_modules::pitch::PitchShift, &mut effect_map);
+    add_effect(effect_modules::saturate::Saturate, &mut effect_map);
+    add_effect(effect_modules::bitcrush::Bitcrush, &mut effect_map);
     // <-- HERE IS WHERE YOU ADD EFFECTS//
 
     let args = Args::parse();
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Analyze(command_args) => commands::analyze::run(command_args),
+            Command::Render(command_args) => commands::render::run(command_args, &effect_map),
+            Command::Response(command_args) => commands::response::run(command_args, &effect_map),
+            Command::Diff(command_args) => commands::diff::run(command_args),
+            Command::Concat(command_args) => commands::concat::run(command_args),
+            Command::Split(command_args) => commands::split::run(command_args),
+        };
+
+        if let Err(message) = result {
+            error(message, ErrorKind::Io);
+        }
+        return;
+    }
+
+    let file_path = args.file_path.unwrap();
     if args.output.is_some() && args.overwrite {
         error("Cannot use output (-o) and overwrite (--overwrite) at the same time".to_string(), ErrorKind::ArgumentConflict);
         return;
@@ -71,39 +154,26 @@ fn main() {
         return;
     }
 
-    let effect_chain = match parse_utils::parse_effects(&args.effects) {
+    let mut effect_chain = match chain::prepare_chain(&args.effects, &effect_map, &args.tail) {
         Ok(v) => v,
-        Err(message) => {
-            error(message, ErrorKind::InvalidValue);
+        Err((message, kind)) => {
+            error(message, kind);
             return;
         }
     };
 
-    for effect_spec in effect_chain.iter() {
-        match effect_map.get(&effect_spec.name) {
-            Some(effect) => {
-                match effect.validate_arguments(&effect_spec.arguments, &args.tail) {
-                    Ok(_) => {},
-                    Err(message) => {
-                        error(format!("{} -> {message}", effect.get_name()), ErrorKind::InvalidValue);
-                        return;
-                    }
-                }
-            },
-            None => {
-                error(format!("Unknown effect '{}'", effect_spec.name), ErrorKind::UnknownArgument);
-                return;
-            }
-        }
-    }
-
-    eprintln!("{}", format!("Reading file {:#?}", &args.file_path).bold());
+    eprintln!("{}", format!("Reading file {:#?}", &file_path).bold());
 
-    let mut buffer = match decoder::read_file(&args.file_path) {
+    let mut buffer = match decoder::read_file(&file_path) {
         Ok(val) => val,
         Err(e) => {error(e, ErrorKind::Io); return;}
     };
 
+    if let Err(message) = decoder::read_cues(&file_path).and_then(|cues| region::resolve_cues(&mut effect_chain, &cues)) {
+        error(message, ErrorKind::InvalidValue);
+        return;
+    }
+
     let spec = &buffer.spec;
 
     let message = format!("   Sample rate: {},\n   Duration: {}s,\n   Bit depth: {},\n   Sample format: {},\n   Channels: {}", 
@@ -116,51 +186,18 @@ fn main() {
 
     eprintln!("{message}\n");
 
-    for effect_spec in effect_chain.iter() {
-        let effect = effect_map.get(&effect_spec.name).unwrap();
-        let bar = ProgressBar::new_spinner();
-        bar.enable_steady_tick(Duration::from_millis(100));
-        bar.set_style(
-            ProgressStyle::with_template(format!("Applying effect '{}' {{msg}}{{spinner}}", effect_spec.name).as_str())
-            .unwrap()
-        );
-        
-        let result = effect.apply_effect(&mut buffer, &effect_spec.arguments, &args.tail);
-
-        let message;
-        match result {
-            Ok(m) => { message = m },
-            Err(message) => {
-                bar.finish_with_message(format!("{}", "failed".red()));
-                error(message, ErrorKind::Io);
-                return;
-            }
-        }
-        match audio_utils::sanitize_buffer(&mut buffer) {
-            Ok(_) => {
-                if message.is_some() {
-                    bar.finish_with_message(format!("... {} {}", "done".green(), format!("({})", message.unwrap()).yellow()));
-                } else {
-                    bar.finish_with_message(format!("... {}", "done".green()));
-                }
-
-            },
-            Err(message) => {
-                bar.finish_with_message(format!("... {}","failed".red()));
-                error(message, ErrorKind::ValueValidation);
-                return;
-            }
-        }
+    if let Err((message, kind)) = chain::apply_chain(&mut buffer, &effect_chain, &effect_map, &args.tail) {
+        error(message, kind);
+        return;
     }
     
     eprintln!("\n");
     
-    let path;
-    if args.overwrite {
-        path = args.file_path;
+    let path = if args.overwrite {
+        file_path
     } else {
-        path = args.output.unwrap();
-    }
+        args.output.unwrap()
+    };
 
     let bar = ProgressBar::new_spinner();
     bar.enable_steady_tick(Duration::from_millis(100));
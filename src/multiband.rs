@@ -0,0 +1,110 @@
+use crate::{biquad::apply_biquads, effect_modules::filter::{design_sections, FilterType}, parse_utils::verify_range, types::{AudioBuffer, BiquadCoefficients, EffectSpec}};
+
+pub const NAME: &str = "multiband";
+const FREQ_PREFIX: &str = "f";
+const CROSSOVER_ORDER: usize = 4;
+
+/// Reads the crossover frequencies `f1`, `f2`, ... and checks there is a sub-chain for every band
+pub fn crossovers(spec: &EffectSpec, band_count: usize) -> Result<Vec<f64>, String> {
+    if let Some(argument) = spec.keywords.keys().chain(spec.times.keys()).next() {
+        return Err(format!("Argument '{argument}' must be a number"));
+    }
+
+    let mut count = 0;
+    for argument in spec.arguments.keys() {
+        match argument.strip_prefix(FREQ_PREFIX).and_then(|n| n.parse::<usize>().ok()) {
+            Some(index) if index > 0 => count = count.max(index),
+            _ => return Err(format!("Unknown argument '{argument}'")),
+        }
+    }
+    if count == 0 {
+        return Err(format!("Missing argument '{FREQ_PREFIX}1'"));
+    }
+
+    let mut frequencies: Vec<f64> = Vec::with_capacity(count);
+    for index in 1..=count {
+        let key = format!("{FREQ_PREFIX}{index}");
+        let freq = verify_range(&key, 1.0, 20000.0, &spec.arguments)?;
+        if let Some(previous) = frequencies.last() && freq <= *previous {
+            return Err(format!("Crossover frequencies must be ascending ('{key}' is not above {previous} Hz)"));
+        }
+        frequencies.push(freq);
+    }
+
+    if band_count != count + 1 {
+        return Err(format!("{} crossover(s) need {} bands, got {band_count}", count, count + 1));
+    }
+
+    Ok(frequencies)
+}
+
+fn crossover_sections(freq: f64, sample_rate: u32, highpass: bool) -> Vec<BiquadCoefficients> {
+    design_sections(FilterType::LinkwitzRiley, CROSSOVER_ORDER, 0.0, freq, sample_rate, highpass)
+}
+
+fn copy_buffer(buffer: &AudioBuffer) -> AudioBuffer {
+    AudioBuffer { spec: buffer.spec, channels: buffer.channels.clone() }
+}
+
+/// Runs the buffer through the lowpass and highpass of a crossover and sums them,
+/// which only shifts the phase the way the crossover does
+fn crossover_allpass(buffer: &mut AudioBuffer, freq: f64) {
+    let sample_rate = buffer.spec.sample_rate;
+    let mut high = copy_buffer(buffer);
+    apply_biquads(buffer, &crossover_sections(freq, sample_rate, false));
+    apply_biquads(&mut high, &crossover_sections(freq, sample_rate, true));
+
+    for (channel, high_channel) in buffer.channels.iter_mut().zip(high.channels.iter()) {
+        for (sample, high_sample) in channel.iter_mut().zip(high_channel.iter()) {
+            *sample += high_sample;
+        }
+    }
+}
+
+/// Splits the buffer into bands with Linkwitz-Riley crossovers, lowest band first.
+/// Every band also goes through the allpass of the crossovers it was split off before,
+/// so all bands share the same phase and sum back to an allpassed copy of the input.
+pub fn split(buffer: &AudioBuffer, crossovers: &[f64]) -> Result<Vec<AudioBuffer>, String> {
+    let sample_rate = buffer.spec.sample_rate;
+    if let Some(freq) = crossovers.iter().find(|f| **f >= sample_rate as f64 / 2.0) {
+        return Err(format!("Crossover at {freq} Hz is above the Nyquist frequency"));
+    }
+
+    let mut bands = Vec::with_capacity(crossovers.len() + 1);
+    let mut rest = copy_buffer(buffer);
+
+    for (index, freq) in crossovers.iter().enumerate() {
+        let mut low = copy_buffer(&rest);
+        apply_biquads(&mut low, &crossover_sections(*freq, sample_rate, false));
+        apply_biquads(&mut rest, &crossover_sections(*freq, sample_rate, true));
+
+        for later in crossovers[index + 1..].iter() {
+            crossover_allpass(&mut low, *later);
+        }
+        bands.push(low);
+    }
+    bands.push(rest);
+
+    Ok(bands)
+}
+
+/// Sums the processed bands, bands that got longer (like a delay tail) extend the result
+pub fn sum(bands: &[AudioBuffer]) -> Result<AudioBuffer, String> {
+    let first = &bands[0];
+    if bands.iter().any(|band| band.channels.len() != first.channels.len()) {
+        return Err("All bands must keep the same amount of channels".to_string());
+    }
+
+    let length = bands.iter().map(|band| band.channels[0].len()).max().unwrap_or(0);
+    let mut result = AudioBuffer { spec: first.spec, channels: vec![vec![0.0; length]; first.channels.len()] };
+
+    for band in bands.iter() {
+        for (channel, band_channel) in result.channels.iter_mut().zip(band.channels.iter()) {
+            for (sample, band_sample) in channel.iter_mut().zip(band_channel.iter()) {
+                *sample += band_sample;
+            }
+        }
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,92 @@
+use core::f64;
+
+/// Half of the taps of every half-band filter around the center tap, which puts the stopband below -100 dB
+const HALF_BAND_CENTER: usize = 31;
+
+pub const FACTORS: &[usize] = &[1, 2, 4, 8];
+
+/// Half-band low-pass filter that doubles or halves the sample rate. Every other tap of a half-band filter is zero
+/// and the center tap is 0.5, so only the `taps` (the even ones) are stored, the other phase is a plain delay.
+struct HalfBand {
+    taps: Vec<f64>,
+}
+
+impl HalfBand {
+    fn new() -> HalfBand {
+        let length = 2 * HALF_BAND_CENTER + 1;
+        let taps = (0..length).step_by(2).map(|n| {
+            let t = n as f64 - HALF_BAND_CENTER as f64;
+            let sinc = (f64::consts::FRAC_PI_2 * t).sin() / (f64::consts::PI * t);
+            // Blackman-Harris window
+            let phase = f64::consts::TAU * n as f64 / (length - 1) as f64;
+            let window = 0.35875 - 0.48829 * phase.cos() + 0.14128 * (2.0 * phase).cos() - 0.01168 * (3.0 * phase).cos();
+            sinc * window
+        }).collect();
+
+        HalfBand { taps }
+    }
+
+    /// Twice as many samples, delayed by `HALF_BAND_CENTER` samples of the new rate
+    fn upsample(&self, input: &[f64]) -> Vec<f64> {
+        let at = |index: isize| if index >= 0 { input.get(index as usize).copied().unwrap_or(0.0) } else { 0.0 };
+        let mut output = Vec::with_capacity(input.len() * 2);
+
+        for n in 0..input.len() as isize {
+            let filtered: f64 = self.taps.iter().enumerate().map(|(k, tap)| tap * at(n - k as isize)).sum();
+            output.push(2.0 * filtered);
+            output.push(at(n - HALF_BAND_CENTER as isize / 2));
+        }
+
+        output
+    }
+
+    /// Half as many samples, delayed by `HALF_BAND_CENTER` samples of the old rate
+    fn downsample(&self, input: &[f64]) -> Vec<f64> {
+        let at = |index: isize| if index >= 0 { input.get(index as usize).copied().unwrap_or(0.0) } else { 0.0 };
+
+        (0..(input.len() / 2) as isize).map(|n| {
+            let filtered: f64 = self.taps.iter().enumerate().map(|(k, tap)| tap * at(2 * n - 2 * k as isize)).sum();
+            filtered + 0.5 * at(2 * n - HALF_BAND_CENTER as isize)
+        }).collect()
+    }
+}
+
+/// Runs `shaper` on every sample of the channel at `factor` (1, 2, 4 or 8) times the sample rate. The signal goes up
+/// and down through a cascade of half-band filters, so the harmonics a nonlinearity adds above the original Nyquist
+/// frequency are filtered out instead of aliasing back. The filter delay is compensated and the length is kept.
+pub fn process<F: FnMut(f64) -> f64>(channel: &mut Vec<f64>, factor: usize, mut shaper: F) {
+    if factor <= 1 {
+        for sample in channel.iter_mut() {
+            *sample = shaper(*sample);
+        }
+        return;
+    }
+
+    let filter = HalfBand::new();
+    let stages = factor.trailing_zeros();
+    let length = channel.len();
+
+    // Both directions delay by the center tap at every rate, counted here in samples of the highest rate
+    let latency = 2 * HALF_BAND_CENTER * (factor - 1);
+    let padding = latency / factor + 2;
+
+    let mut signal = channel.clone();
+    signal.resize(length + padding, 0.0);
+    for _ in 0..stages {
+        signal = filter.upsample(&signal);
+    }
+
+    for sample in signal.iter_mut() {
+        *sample = shaper(*sample);
+    }
+
+    // The part of the delay that isn't a whole number of original samples is removed at the highest rate
+    signal.drain(..latency % factor);
+    for _ in 0..stages {
+        signal = filter.downsample(&signal);
+    }
+
+    signal.drain(..latency / factor);
+    signal.resize(length, 0.0);
+    *channel = signal;
+}
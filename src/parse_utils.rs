@@ -1,14 +1,49 @@
-use std::{collections::HashMap, num::ParseFloatError};
+use std::collections::HashMap;
 
-use crate::types::{EffectSpec};
+use crate::{multiband, routing, types::{ChainNode, ChannelTarget, EffectSpec, Envelope, Region, RegionBound, TimeValue}};
+
+/// Splits on a separator, except between parentheses like in `env(0s:0,1s:-6)`
+fn split_outside_parentheses(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, character) in input.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth <= 0 => {
+                parts.push(&input[start..index]);
+                start = index + c.len_utf8();
+            },
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
 
 fn parse_effect_spec(input: &str) -> Result<EffectSpec, String> {
-    let buffer: Vec<&str> = input.split(":").collect();
+    let buffer: Vec<&str> = split_outside_parentheses(input, ':');
     let mut arguments: HashMap<String, f64> = HashMap::new();
+    let mut keywords: HashMap<String, String> = HashMap::new();
+    let mut times: HashMap<String, TimeValue> = HashMap::new();
+    let mut envelopes: HashMap<String, Envelope> = HashMap::new();
+
+    let (head, region) = match buffer[0].split_once('[') {
+        Some((name, rest)) => match rest.split_once(']') {
+            Some((region, after)) => (format!("{name}{after}"), Some(parse_region(region, name.trim())?)),
+            None => return Err(format!("Missing ']' ({})", name.trim())),
+        },
+        None => (buffer[0].to_string(), None),
+    };
 
-    let effect_name = buffer[0].trim().to_lowercase();
+    let (effect_name, target) = match head.split_once('@') {
+        Some((name, modifier)) => (name.trim().to_lowercase(), Some(parse_target(modifier, name)?)),
+        None => (head.trim().to_lowercase(), None),
+    };
     if effect_name.is_empty() {
-        return Err(format!("empty effect name"));
+        return Err("empty effect name".to_string());
     }
 
     for arg in buffer.iter().skip(1) {
@@ -17,31 +52,357 @@ fn parse_effect_spec(input: &str) -> Result<EffectSpec, String> {
         }
 
         let pair: Vec<&str> = arg.split("=").collect();
-        match pair.len() {
-            2 => arguments.insert(
-                pair[0].trim().to_lowercase(),
-                pair[1].trim().to_lowercase().parse().map_err(|e: ParseFloatError| e.to_string())?),
-            _ => return Err(format!("Malformed argument '{arg}' ({effect_name})"))
-        };
+        if pair.len() != 2 {
+            return Err(format!("Malformed argument '{arg}' ({effect_name})"));
+        }
+
+        // Non-numerical values are resolved by the effect later on
+        let key = pair[0].trim().to_lowercase();
+
+        // Envelopes are taken before lowercasing, as they can hold a file path
+        if let Some(text) = pair[1].trim().strip_prefix("env(").and_then(|rest| rest.strip_suffix(')')) {
+            envelopes.insert(key, parse_envelope(text, &effect_name)?);
+            continue;
+        }
+        let value = pair[1].trim().to_lowercase();
+
+        // Lists like `map=1,0` become numbered arguments `map#1`, `map#2`, ...
+        if value.contains(',') {
+            for (index, item) in value.split(',').enumerate() {
+                match item.trim().parse::<f64>() {
+                    Ok(number) => { arguments.insert(list_key(&key, index + 1), number); },
+                    Err(_) => return Err(format!("Malformed list '{value}' ({effect_name})")),
+                }
+            }
+            continue;
+        }
+
+        match (value.parse::<f64>(), parse_time(&value)) {
+            (Ok(number), _) => { arguments.insert(key, number); },
+            (Err(_), Some(time)) => { times.insert(key, time); },
+            (Err(_), None) => { keywords.insert(key, value); }
+        }
     }
 
-    return Ok(
+    Ok(
         EffectSpec {
             name: effect_name,
-            arguments: arguments
+            arguments,
+            keywords,
+            times,
+            envelopes,
+            mix: None,
+            target,
+            region
         }
-    );
+    )
 }
 
-pub fn parse_effects(input: &Vec<String>) -> Result<Vec<EffectSpec>, String> {
-    let mut result: Vec<EffectSpec> = Vec::new();
-    
-    for effect in input.iter() {
-       let spec = parse_effect_spec(effect)?;
-       result.push(spec); 
+/// Parses numbers followed by `smp`, `ms` or `s`
+fn parse_time(value: &str) -> Option<TimeValue> {
+    if let Some(number) = value.strip_suffix("smp") {
+        return number.trim().parse::<f64>().ok().map(TimeValue::Samples);
+    }
+    if let Some(number) = value.strip_suffix("ms") {
+        return number.trim().parse::<f64>().ok().map(|ms| TimeValue::Seconds(ms / 1000.0));
+    }
+    value.strip_suffix('s')?.trim().parse::<f64>().ok().map(TimeValue::Seconds)
+}
+
+/// Parses the breakpoints of `env(0s:-inf,2s:0)`. Without any ':' the text is the path of a breakpoint file,
+/// with one `time value` pair per line and '#' comments.
+fn parse_envelope(text: &str, effect_name: &str) -> Result<Envelope, String> {
+    if text.trim().is_empty() {
+        return Err(format!("Empty envelope ({effect_name})"));
+    }
+
+    let breakpoints: Vec<String> = if text.contains(':') {
+        text.split(',').map(|point| point.to_string()).collect()
+    } else {
+        let path = text.trim();
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Couldn't read breakpoint file '{path}' ({effect_name}): {e}"))?;
+        content.lines()
+            .map(|line| line.split('#').next().unwrap().replace([',', ':'], " "))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(":"))
+            .collect()
+    };
+
+    let mut points = Vec::new();
+    for breakpoint in breakpoints.iter() {
+        let malformed = || format!("Malformed breakpoint '{}' ({effect_name}), expected 'time:value'", breakpoint.trim());
+        let (time, value) = breakpoint.split_once(':').ok_or_else(malformed)?;
+        let time = time.trim().to_lowercase();
+        let time = match time.parse::<f64>() {
+            Ok(seconds) => TimeValue::Seconds(seconds),
+            Err(_) => parse_time(&time).ok_or_else(malformed)?,
+        };
+        let value = value.trim().parse::<f64>().map_err(|_| malformed())?;
+
+        if let TimeValue::Seconds(t) | TimeValue::Samples(t) = time && t < 0.0 {
+            return Err(format!("Breakpoint times can't be negative ({effect_name})"));
+        }
+        if value.is_nan() || value == f64::INFINITY {
+            return Err(format!("Breakpoint values must be numbers or -inf ({effect_name})"));
+        }
+        points.push((time, value));
+    }
+
+    if points.is_empty() {
+        return Err(format!("Empty breakpoint file '{}' ({effect_name})", text.trim()));
+    }
+
+    // Without the sample rate, samples can't be put in order with the other units
+    for pair in points.windows(2) {
+        match (pair[0].0, pair[1].0) {
+            (TimeValue::Seconds(from), TimeValue::Seconds(to)) | (TimeValue::Samples(from), TimeValue::Samples(to)) if to < from =>
+                return Err(format!("Envelope breakpoints must be in chronological order ({effect_name})")),
+            (TimeValue::Seconds(_), TimeValue::Samples(_)) | (TimeValue::Samples(_), TimeValue::Seconds(_)) =>
+                return Err(format!("Envelope breakpoints can't mix samples with other time units ({effect_name})")),
+            _ => {},
+        }
+    }
+    Ok(Envelope { points })
+}
+
+/// Parses the `@` modifier of an effect name: `ch=0,1`, `mid`, `side`, `ms=mid` or `ms=side`
+fn parse_target(modifier: &str, effect_name: &str) -> Result<ChannelTarget, String> {
+    let modifier = modifier.trim().to_lowercase();
+    let (key, value) = match modifier.split_once('=') {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => ("ms", modifier.as_str()),
+    };
+
+    match (key, value) {
+        ("ms", "mid") => Ok(ChannelTarget::Mid),
+        ("ms", "side") => Ok(ChannelTarget::Side),
+        ("ch", list) => {
+            let channels: Result<Vec<usize>, _> = list.split(',').map(|c| c.trim().parse::<usize>()).collect();
+            match channels {
+                Ok(channels) if (1..channels.len()).any(|i| channels[..i].contains(&channels[i])) => {
+                    Err(format!("Channel list '{list}' contains duplicates ({effect_name})"))
+                },
+                Ok(channels) => Ok(ChannelTarget::Channels(channels)),
+                Err(_) => Err(format!("Malformed channel list '{list}' ({effect_name})")),
+            }
+        },
+        _ => Err(format!("Unknown modifier '@{modifier}' ({effect_name}), expected '@ch=...', '@mid' or '@side'")),
+    }
+}
+
+/// Parses the region of an effect name like `[1.5s..3s]`, `[..500ms]` or `[verse..chorus]`
+fn parse_region(text: &str, effect_name: &str) -> Result<Region, String> {
+    let Some((start, end)) = text.split_once("..") else {
+        return Err(format!("Malformed region '[{text}]' ({effect_name}), expected '[start..end]'"));
+    };
+
+    Ok(Region {
+        start: parse_region_bound(start, effect_name)?,
+        end: parse_region_bound(end, effect_name)?,
+    })
+}
+
+/// A bound is a time (bare numbers are seconds), a cue name or nothing
+fn parse_region_bound(text: &str, effect_name: &str) -> Result<Option<RegionBound>, String> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    let time = match text.parse::<f64>() {
+        Ok(seconds) => Some(TimeValue::Seconds(seconds)),
+        Err(_) => parse_time(&text),
+    };
+
+    match time {
+        Some(TimeValue::Seconds(value) | TimeValue::Samples(value)) if value < 0.0 => {
+            Err(format!("Region bound '{text}' can't be negative ({effect_name})"))
+        },
+        Some(TimeValue::Samples(samples)) if samples.fract() != 0.0 => {
+            Err(format!("Region bound '{text}' must be a whole number of samples ({effect_name})"))
+        },
+        Some(time) => Ok(Some(RegionBound::Time(time))),
+        None => Ok(Some(RegionBound::Cue(text))),
+    }
+}
+
+/// Specs that take the group following them, a group after any other spec runs in parallel
+const GROUP_EFFECTS: [&str; 2] = [multiband::NAME, routing::RETURN_NAME];
+
+enum Token {
+    Spec(String),
+    Open,
+    Separator,
+    Close,
+}
+
+/// Specs within one argument are separated by whitespace, spaces around ':' and '=' and within parentheses are ignored
+fn push_specs(tokens: &mut Vec<Token>, text: &str) {
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let in_parentheses = current.matches('(').count() > current.matches(')').count();
+        let joined = in_parentheses || current.ends_with([':', '=']) || word.starts_with([':', '=']);
+        if !current.is_empty() && !joined {
+            tokens.push(Token::Spec(std::mem::take(&mut current)));
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        tokens.push(Token::Spec(current));
+    }
+}
+
+/// Splits the arguments into effect specs and the `[`, `|` and `]` group delimiters.
+/// A `[` right after an effect name, without a space, starts its region and stays part of the spec.
+fn tokenize(input: &[String]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for argument in input.iter() {
+        let mut text = String::new();
+        let mut in_region = false;
+        for character in argument.chars() {
+            if in_region || (character == '[' && text.ends_with(|c: char| !c.is_whitespace())) {
+                in_region = character != ']';
+                text.push(character);
+                continue;
+            }
+
+            let delimiter = match character {
+                '[' => Token::Open,
+                '|' => Token::Separator,
+                ']' => Token::Close,
+                _ => {
+                    text.push(character);
+                    continue;
+                }
+            };
+            push_specs(&mut tokens, &text);
+            text.clear();
+            tokens.push(delimiter);
+        }
+        push_specs(&mut tokens, &text);
+    }
+
+    tokens
+}
+
+/// Parses a chain until a `|` or `]`, which is left for the caller
+fn parse_chain(tokens: &[Token], position: &mut usize) -> Result<Vec<ChainNode>, String> {
+    let mut chain = Vec::new();
+
+    while let Some(token) = tokens.get(*position) {
+        match token {
+            Token::Spec(text) => {
+                let spec = parse_effect_spec(text)?;
+                *position += 1;
+                if GROUP_EFFECTS.contains(&spec.name.as_str()) && let Some(Token::Open) = tokens.get(*position) {
+                    *position += 1;
+                    let branches = parse_group(tokens, position, &spec.name)?;
+                    chain.push(ChainNode::Group(spec, branches));
+                } else {
+                    chain.push(ChainNode::Effect(spec));
+                }
+            },
+            Token::Open => {
+                *position += 1;
+                chain.push(ChainNode::Parallel(parse_group(tokens, position, "parallel group")?));
+            },
+            Token::Separator | Token::Close => break,
+        }
+    }
+
+    Ok(chain)
+}
+
+fn parse_group(tokens: &[Token], position: &mut usize, name: &str) -> Result<Vec<Vec<ChainNode>>, String> {
+    let mut branches = Vec::new();
+
+    loop {
+        branches.push(parse_chain(tokens, position)?);
+        match tokens.get(*position) {
+            Some(Token::Separator) => *position += 1,
+            Some(Token::Close) => {
+                *position += 1;
+                return Ok(branches);
+            },
+            _ => return Err(format!("Missing ']' ({name})")),
+        }
+    }
+}
+
+pub fn parse_effects(input: &[String]) -> Result<Vec<ChainNode>, String> {
+    let tokens = tokenize(input);
+    let mut position = 0;
+    let chain = parse_chain(&tokens, &mut position)?;
+
+    match tokens.get(position) {
+        None => Ok(chain),
+        Some(Token::Separator) => Err("Unexpected '|' outside of a group".to_string()),
+        Some(_) => Err("Unexpected ']' without a matching '['".to_string()),
+    }
+}
+
+/// Key of a list item, which can't be mistaken for numbered arguments like the bands of `parametric` (`freq1`)
+fn list_key(thing: &str, index: usize) -> String {
+    format!("{thing}#{index}")
+}
+
+/// Reads a list argument, written either as a single value (`map=1`) or as a list (`map=1,0`)
+pub fn list_argument(thing: &str, map: &HashMap<String, f64>) -> Result<Vec<f64>, String> {
+    if let Some(value) = map.get(thing) {
+        return Ok(vec![*value]);
+    }
+
+    let items: Vec<f64> = (1..).map_while(|index| map.get(&list_key(thing, index)).copied()).collect();
+    if items.is_empty() {
+        return Err(format!("Missing argument '{thing}'"));
+    }
+    Ok(items)
+}
+
+/// Time arguments given in samples are stored under their name followed by this suffix
+pub const SAMPLES_SUFFIX: &str = "_smp";
+
+/// Checks that a time argument exists, in seconds or in samples, and isn't negative
+pub fn verify_time(thing: &str, map: &HashMap<String, f64>) -> Result<(), String> {
+    let samples_key = format!("{thing}{SAMPLES_SUFFIX}");
+    let value = match (map.get(thing), map.get(&samples_key)) {
+        (Some(seconds), _) => *seconds,
+        (None, Some(samples)) => {
+            if samples.fract() != 0.0 {
+                return Err(format!("Argument '{thing}' must be a whole number of samples"));
+            }
+            *samples
+        },
+        (None, None) => return Err(format!("Missing argument '{thing}'")),
     };
 
-    return Ok(result);
+    if value < 0.0 {
+        return Err(format!("Argument '{thing}' can't be negative"));
+    }
+    Ok(())
+}
+
+/// Reads a time argument checked with `verify_time` as a number of samples
+pub fn time_in_samples(thing: &str, map: &HashMap<String, f64>, sample_rate: u32) -> usize {
+    match map.get(thing) {
+        Some(seconds) => (seconds * sample_rate as f64).round() as usize,
+        None => *map.get(&format!("{thing}{SAMPLES_SUFFIX}")).unwrap() as usize,
+    }
+}
+
+/// Position of a keyword argument in its keyword list, which is how the chain stores keywords
+pub fn keyword_index(value: f64, keywords: &[&str]) -> Result<usize, String> {
+    match value.fract() == 0.0 && value >= 0.0 && (value as usize) < keywords.len() {
+        true => Ok(value as usize),
+        false => Err(format!("Unknown keyword index {value}, expected one of {}", keywords.join(", "))),
+    }
+}
+
+/// The variant a keyword argument stands for, `variants` follows the order of `keywords`
+pub fn keyword_variant<T: Copy>(value: f64, keywords: &[&str], variants: &[T]) -> Result<T, String> {
+    let index = keyword_index(value, keywords)?;
+    variants.get(index).copied().ok_or_else(|| format!("Keyword '{}' has no variant", keywords[index]))
 }
 
 pub fn arg_exists(thing: &String, map: &HashMap<String, f64>) -> Result<f64, String> {
@@ -0,0 +1,260 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use crate::{spectrum, types::AudioBuffer};
+
+const BACKGROUND: [u8; 3] = [18, 18, 22];
+const GRID: [u8; 3] = [60, 60, 72];
+const WAVEFORM: [u8; 3] = [90, 170, 255];
+const SEPARATOR: [u8; 3] = [140, 140, 150];
+const RESPONSE_PHASE: [u8; 3] = [250, 170, 60];
+const SPECTROGRAM_FLOOR_DB: f64 = -120.0;
+const SPECTROGRAM_MIN_FREQ: f64 = 20.0;
+const GRID_FREQUENCIES: [f64; 3] = [100.0, 1000.0, 10000.0];
+const SIDE_BY_SIDE_GAP: u32 = 4;
+
+// Dark to bright gradient used for dB values, from the floor (0.0) up to 0 dB (1.0)
+const COLORMAP: [(f64, [u8; 3]); 6] = [
+    (0.0, [0, 0, 4]),
+    (0.25, [60, 15, 110]),
+    (0.5, [150, 40, 100]),
+    (0.7, [230, 90, 40]),
+    (0.9, [250, 200, 40]),
+    (1.0, [252, 255, 200]),
+];
+
+/// Largest width or height accepted for an image, a side by side image is about twice as wide
+pub const MAX_DIMENSION: u32 = 16384;
+
+pub fn verify_dimensions(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!("Image dimensions must be between 1 and {MAX_DIMENSION} pixels"));
+    }
+    Ok(())
+}
+
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Canvas {
+        let area = width as usize * height as usize;
+        let mut pixels = Vec::with_capacity(area * 3);
+        for _ in 0..area {
+            pixels.extend_from_slice(&BACKGROUND);
+        }
+        Canvas { width, height, pixels }
+    }
+
+    pub fn set_pixel(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 3;
+        self.pixels[index..index + 3].copy_from_slice(&color);
+    }
+
+    pub fn vertical_line(&mut self, x: i64, y0: i64, y1: i64, color: [u8; 3]) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    pub fn line(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, color: [u8; 3]) {
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            self.set_pixel((x0 + t * (x1 - x0)).round() as i64, (y0 + t * (y1 - y0)).round() as i64, color);
+        }
+    }
+
+    pub fn horizontal_line(&mut self, y: i64, color: [u8; 3]) {
+        for x in 0..self.width as i64 {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// Joins two canvases horizontally, separated by a thin divider
+    pub fn side_by_side(left: &Canvas, right: &Canvas) -> Canvas {
+        let mut canvas = Canvas::new(left.width + SIDE_BY_SIDE_GAP + right.width, left.height.max(right.height));
+        canvas.blit(left, 0);
+        canvas.blit(right, left.width + SIDE_BY_SIDE_GAP);
+        for x in left.width..left.width + SIDE_BY_SIDE_GAP {
+            canvas.vertical_line(x as i64, 0, canvas.height as i64 - 1, SEPARATOR);
+        }
+        canvas
+    }
+
+    fn blit(&mut self, other: &Canvas, x_offset: u32) {
+        for y in 0..other.height {
+            let source = y as usize * other.width as usize * 3;
+            let target = (y as usize * self.width as usize + x_offset as usize) * 3;
+            let row = other.width as usize * 3;
+            self.pixels[target..target + row].copy_from_slice(&other.pixels[source..source + row]);
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&self.pixels).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())
+    }
+}
+
+fn colormap(value: f64) -> [u8; 3] {
+    let value = value.clamp(0.0, 1.0);
+    for pair in COLORMAP.windows(2) {
+        let (start, from) = pair[0];
+        let (end, to) = pair[1];
+        if value <= end {
+            let t = (value - start) / (end - start);
+            return [0, 1, 2].map(|i| (from[i] as f64 + t * (to[i] as f64 - from[i] as f64)) as u8);
+        }
+    }
+    COLORMAP[COLORMAP.len() - 1].1
+}
+
+/// Min/max envelope of every channel, stacked vertically
+pub fn render_waveform(buffer: &AudioBuffer, width: u32, height: u32) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    let lane_height = height as f64 / buffer.channels.len() as f64;
+
+    for (index, channel) in buffer.channels.iter().enumerate() {
+        let top = index as f64 * lane_height;
+        let center = top + lane_height / 2.0;
+        let to_y = |value: f64| (center - value.clamp(-1.0, 1.0) * lane_height / 2.0) as i64;
+
+        canvas.horizontal_line(center as i64, GRID);
+        if index > 0 {
+            canvas.horizontal_line(top as i64, SEPARATOR);
+        }
+
+        for x in 0..width {
+            let start = x as usize * channel.len() / width as usize;
+            let end = ((x as usize + 1) * channel.len() / width as usize).max(start + 1).min(channel.len());
+            if start >= end {
+                continue;
+            }
+
+            let (min, max) = channel[start..end].iter()
+                .fold((f64::MAX, f64::MIN), |(min, max), s| (min.min(*s), max.max(*s)));
+            canvas.vertical_line(x as i64, to_y(max), to_y(min), WAVEFORM);
+        }
+    }
+
+    canvas
+}
+
+/// STFT spectrogram of every channel with a logarithmic frequency axis
+pub fn render_spectrogram(buffer: &AudioBuffer, fft_size: usize, hop: usize, width: u32, height: u32) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    let lane_height = height as f64 / buffer.channels.len() as f64;
+    let fs = buffer.spec.sample_rate as f64;
+    let max_freq = fs / 2.0;
+    let bin_count = fft_size / 2 + 1;
+
+    // Frequency of each pixel row, from the top of a lane (highest) to the bottom (lowest)
+    let lane_pixels = lane_height.ceil() as usize;
+    let row_bins: Vec<f64> = (0..lane_pixels).map(|row| {
+        let position = 1.0 - (row as f64 + 0.5) / lane_height;
+        let freq = SPECTROGRAM_MIN_FREQ * (max_freq / SPECTROGRAM_MIN_FREQ).powf(position);
+        (freq / fs * fft_size as f64).min((bin_count - 1) as f64)
+    }).collect();
+
+    for (index, channel) in buffer.channels.iter().enumerate() {
+        let frames = spectrum::stft(channel, fft_size, hop);
+        let top = index as f64 * lane_height;
+
+        for x in 0..width {
+            let start = x as usize * frames.len() / width as usize;
+            let end = ((x as usize + 1) * frames.len() / width as usize).max(start + 1).min(frames.len());
+
+            for (row, bin) in row_bins.iter().enumerate() {
+                let y = top + row as f64;
+                if y >= top + lane_height {
+                    break;
+                }
+
+                let lower = bin.floor() as usize;
+                let upper = (lower + 1).min(bin_count - 1);
+                let fraction = bin - lower as f64;
+                let magnitude = frames[start..end].iter()
+                    .map(|frame| frame[lower] * (1.0 - fraction) + frame[upper] * fraction)
+                    .fold(0.0, f64::max);
+
+                let db = 20.0 * magnitude.log10();
+                canvas.set_pixel(x as i64, y as i64, colormap(1.0 - db / SPECTROGRAM_FLOOR_DB));
+            }
+        }
+
+        for freq in GRID_FREQUENCIES.iter().filter(|f| **f < max_freq) {
+            let position = (freq / SPECTROGRAM_MIN_FREQ).ln() / (max_freq / SPECTROGRAM_MIN_FREQ).ln();
+            let y = top + (1.0 - position) * lane_height;
+            for x in (0..width as i64).step_by(4) {
+                canvas.set_pixel(x, y as i64, GRID);
+            }
+        }
+
+        if index > 0 {
+            canvas.horizontal_line(top as i64, SEPARATOR);
+        }
+    }
+
+    canvas
+}
+
+/// Magnitude (top half) and phase (bottom half) of a frequency response on a logarithmic frequency axis
+pub fn render_response(frequencies: &[f64], magnitudes_db: &[f64], phases_deg: &[f64], width: u32, height: u32) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    let half = height as f64 / 2.0;
+    let min_freq = frequencies[0];
+    let max_freq = frequencies[frequencies.len() - 1];
+    let to_x = |freq: f64| (freq / min_freq).ln() / (max_freq / min_freq).ln() * (width as f64 - 1.0);
+
+    // The magnitude range grows in 6 dB steps so small curves stay readable
+    let largest = magnitudes_db.iter().filter(|m| m.is_finite()).fold(0.0, |max: f64, m| max.max(m.abs()));
+    let range = ((largest / 6.0).ceil() * 6.0).max(12.0);
+    let magnitude_y = |db: f64| half / 2.0 - db.clamp(-range, range) / range * (half / 2.0 - 1.0);
+    let phase_y = |deg: f64| half + half / 2.0 - deg / 180.0 * (half / 2.0 - 1.0);
+
+    let mut decade = 10.0_f64.powf(min_freq.log10().ceil());
+    while decade < max_freq {
+        for multiple in 1..10 {
+            let freq = decade * multiple as f64;
+            if freq > min_freq && freq < max_freq {
+                let color = if multiple == 1 { SEPARATOR } else { GRID };
+                canvas.vertical_line(to_x(freq) as i64, 0, height as i64 - 1, color);
+            }
+        }
+        decade *= 10.0;
+    }
+
+    let mut db = -range;
+    while db <= range {
+        canvas.horizontal_line(magnitude_y(db) as i64, if db == 0.0 { SEPARATOR } else { GRID });
+        db += 6.0;
+    }
+    for deg in [-180.0, -90.0, 0.0, 90.0, 180.0] {
+        canvas.horizontal_line(phase_y(deg) as i64, if deg == 0.0 { SEPARATOR } else { GRID });
+    }
+    canvas.horizontal_line(half as i64, SEPARATOR);
+
+    for i in 1..frequencies.len() {
+        let (x0, x1) = (to_x(frequencies[i - 1]), to_x(frequencies[i]));
+        canvas.line(x0, magnitude_y(magnitudes_db[i - 1]), x1, magnitude_y(magnitudes_db[i]), WAVEFORM);
+
+        // Phase is wrapped, don't connect the jump from +180 to -180 degrees
+        if (phases_deg[i] - phases_deg[i - 1]).abs() < 180.0 {
+            canvas.line(x0, phase_y(phases_deg[i - 1]), x1, phase_y(phases_deg[i]), RESPONSE_PHASE);
+        }
+    }
+
+    canvas
+}
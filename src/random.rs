@@ -0,0 +1,18 @@
+/// Xorshift pseudo random numbers, the same seed always gives the same sequence
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Xorshift {
+        Xorshift { state: seed.max(1) }
+    }
+
+    /// Between -1 and 1
+    pub fn next_value(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,158 @@
+use std::ops::Range;
+
+use crate::types::{AudioBuffer, ChainNode, Cues, EffectSpec, Region, RegionBound, TimeValue};
+
+/// Length of the crossfades on both sides of a region
+const FADE_SECONDS: f64 = 0.005;
+
+fn resolve_spec(effect_spec: &mut EffectSpec, cues: &Cues) -> Result<(), String> {
+    let Some(region) = effect_spec.region.as_mut() else {
+        return Ok(());
+    };
+
+    for bound in [&mut region.start, &mut region.end].into_iter().flatten() {
+        let position = match bound {
+            RegionBound::Cue(name) => *cues.get(name).ok_or_else(|| {
+                let mut names: Vec<&String> = cues.keys().collect();
+                names.sort();
+                let known = match names.is_empty() {
+                    true => "the input file has no cue points".to_string(),
+                    false => format!("expected one of {}", names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")),
+                };
+                format!("{} -> Unknown cue '{name}', {known}", effect_spec.name)
+            })?,
+            RegionBound::Time(_) => continue,
+        };
+        *bound = RegionBound::Time(TimeValue::Samples(position as f64));
+    }
+
+    Ok(())
+}
+
+/// Replaces the cue names in region bounds with their positions in the input file
+pub fn resolve_cues(effect_chain: &mut [ChainNode], cues: &Cues) -> Result<(), String> {
+    for node in effect_chain.iter_mut() {
+        match node {
+            ChainNode::Effect(effect_spec) => resolve_spec(effect_spec, cues)?,
+            ChainNode::Group(effect_spec, branches) => {
+                resolve_spec(effect_spec, cues)?;
+                for branch in branches.iter_mut() {
+                    resolve_cues(branch, cues)?;
+                }
+            },
+            ChainNode::Parallel(branches) => {
+                for branch in branches.iter_mut() {
+                    resolve_cues(branch, cues)?;
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn bound_in_samples(bound: &RegionBound, sample_rate: u32) -> Result<usize, String> {
+    match bound {
+        RegionBound::Time(TimeValue::Seconds(seconds)) => Ok((seconds * sample_rate as f64).round() as usize),
+        RegionBound::Time(TimeValue::Samples(samples)) => Ok(*samples as usize),
+        RegionBound::Cue(name) => Err(format!("Unknown cue '{name}', cue points are only read from the input file")),
+    }
+}
+
+/// Start and end of the region in samples, the end is clamped to the length of the buffer
+pub fn range(buffer: &AudioBuffer, region: &Region) -> Result<Range<usize>, String> {
+    let sample_rate = buffer.spec.sample_rate;
+    let length = buffer.channels[0].len();
+    let start = region.start.as_ref().map(|bound| bound_in_samples(bound, sample_rate)).transpose()?.unwrap_or(0);
+    let end = region.end.as_ref().map(|bound| bound_in_samples(bound, sample_rate)).transpose()?.unwrap_or(length);
+
+    if start >= length {
+        return Err(format!("The region starts past the end of the audio ({length} samples)"));
+    }
+    if end <= start {
+        return Err("The region must end after it starts".to_string());
+    }
+    Ok(start..end.min(length))
+}
+
+/// Copies the region with a short margin on each side. The copy is faded in and out over the margins,
+/// the returned fade is what was applied to it, starting at the returned position.
+pub fn extract(buffer: &AudioBuffer, range: &Range<usize>) -> (AudioBuffer, usize, Vec<f64>) {
+    let fade = ((FADE_SECONDS * buffer.spec.sample_rate as f64).round() as usize).max(1);
+    let start = range.start.saturating_sub(fade);
+    let end = (range.end + fade).min(buffer.channels[0].len());
+
+    let window: Vec<f64> = (start..end).map(|n| {
+        if n < range.start {
+            (n - start + 1) as f64 / (range.start - start + 1) as f64
+        } else if n >= range.end {
+            (end - n) as f64 / (end - range.end + 1) as f64
+        } else {
+            1.0
+        }
+    }).collect();
+
+    let channels = buffer.channels.iter()
+        .map(|channel| channel[start..end].iter().zip(window.iter()).map(|(sample, gain)| sample * gain).collect())
+        .collect();
+
+    (AudioBuffer { spec: buffer.spec, channels }, start, window)
+}
+
+/// Fades the original signal out where the copy was faded in and adds the processed copy, moved back by its latency.
+/// Tails that reach past the region are added to the signal that follows.
+pub fn merge(buffer: &mut AudioBuffer, mut part: AudioBuffer, start: usize, window: &[f64], latency: usize) -> Result<(), String> {
+    if part.channels.len() != buffer.channels.len() {
+        return Err("Effects restricted to a region can't change the amount of channels".to_string());
+    }
+
+    let length = buffer.channels[0].len().max(start + part.channels[0].len().saturating_sub(latency));
+    for (channel, part_channel) in buffer.channels.iter_mut().zip(part.channels.iter_mut()) {
+        part_channel.drain(..latency.min(part_channel.len()));
+        channel.resize(length, 0.0);
+        for (sample, gain) in channel[start..].iter_mut().zip(window.iter()) {
+            *sample *= 1.0 - gain;
+        }
+        for (sample, processed) in channel[start..].iter_mut().zip(part_channel.iter()) {
+            *sample += processed;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the region as it is, for effects whose output replaces it
+pub fn copy(buffer: &AudioBuffer, range: &Range<usize>) -> AudioBuffer {
+    let channels = buffer.channels.iter().map(|channel| channel[range.clone()].to_vec()).collect();
+    AudioBuffer { spec: buffer.spec, channels }
+}
+
+/// Replaces the region with a processed copy of a different length, the signal after it moves along.
+/// The copy fades in over the signal that carried on past the start of the region, and the end of the region
+/// fades in under the end of the copy, so neither join clicks. Joins at the start or end of the audio aren't faded.
+pub fn splice(buffer: &mut AudioBuffer, part: AudioBuffer, range: &Range<usize>) -> Result<(), String> {
+    if part.channels.len() != buffer.channels.len() {
+        return Err("Effects restricted to a region can't change the amount of channels".to_string());
+    }
+
+    let length = buffer.channels[0].len();
+    let part_length = part.channels[0].len();
+    let fade = ((FADE_SECONDS * buffer.spec.sample_rate as f64).round() as usize).min(part_length / 2);
+    let fade_in = if range.start > 0 { fade.min(length - range.start) } else { 0 };
+    let fade_out = if range.end < length { fade.min(range.end) } else { 0 };
+
+    for (channel, mut part_channel) in buffer.channels.iter_mut().zip(part.channels) {
+        for n in 0..fade_in {
+            let gain = (n + 1) as f64 / (fade_in + 1) as f64;
+            part_channel[n] = part_channel[n] * gain + channel[range.start + n] * (1.0 - gain);
+        }
+        for n in 0..fade_out {
+            let gain = (n + 1) as f64 / (fade_out + 1) as f64;
+            let index = part_length - fade_out + n;
+            part_channel[index] = part_channel[index] * (1.0 - gain) + channel[range.end - fade_out + n] * gain;
+        }
+        channel.splice(range.clone(), part_channel);
+    }
+
+    Ok(())
+}
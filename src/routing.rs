@@ -0,0 +1,96 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{parse_utils::arg_exists, types::{AudioBuffer, EffectSpec}};
+
+pub const SEND_NAME: &str = "send";
+pub const RETURN_NAME: &str = "return";
+pub const BUS_ARG: &str = "bus";
+const DB_ARG: &str = "db";
+
+/// Signals sent to every named bus, waiting for their return
+pub type Buses = HashMap<String, AudioBuffer>;
+
+/// Tracks the order of sends and returns while validating, so every bus is sent to before
+/// it is returned and never after (which would make the routing loop)
+#[derive(Default)]
+pub struct BusUsage {
+    sent: HashSet<String>,
+    returned: HashSet<String>,
+}
+
+impl BusUsage {
+    pub fn send(&mut self, bus: &str) -> Result<(), String> {
+        if self.returned.contains(bus) {
+            return Err(format!("Bus '{bus}' is sent to after it was returned"));
+        }
+        self.sent.insert(bus.to_string());
+        Ok(())
+    }
+
+    pub fn receive(&mut self, bus: &str) -> Result<(), String> {
+        if !self.sent.contains(bus) {
+            return Err(format!("Nothing is sent to bus '{bus}' before it is returned"));
+        }
+        if !self.returned.insert(bus.to_string()) {
+            return Err(format!("Bus '{bus}' is returned more than once"));
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<(), String> {
+        match self.sent.iter().find(|bus| !self.returned.contains(*bus)) {
+            Some(bus) => Err(format!("Bus '{bus}' is never returned")),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn is_routing(effect_spec: &EffectSpec) -> bool {
+    effect_spec.name == SEND_NAME || effect_spec.name == RETURN_NAME
+}
+
+/// The bus name is a word, but plain numbers are accepted too
+pub fn bus_name(effect_spec: &EffectSpec) -> Result<String, String> {
+    let allowed = |key: &String| key == BUS_ARG || (effect_spec.name == SEND_NAME && key == DB_ARG);
+    if let Some(argument) = effect_spec.arguments.keys().chain(effect_spec.keywords.keys()).chain(effect_spec.times.keys()).find(|key| !allowed(key)) {
+        return Err(format!("Unknown argument '{argument}'"));
+    }
+
+    match effect_spec.keywords.get(BUS_ARG) {
+        Some(name) => Ok(name.clone()),
+        None => arg_exists(&BUS_ARG.to_string(), &effect_spec.arguments).map(|number| number.to_string()),
+    }
+}
+
+/// Linear gain of a send, from its `db` argument
+pub fn send_gain(effect_spec: &EffectSpec) -> Result<f64, String> {
+    if effect_spec.keywords.contains_key(DB_ARG) {
+        return Err(format!("Argument '{DB_ARG}' must be a number"));
+    }
+    let db = arg_exists(&DB_ARG.to_string(), &effect_spec.arguments)?;
+    Ok(10.0_f64.powf(db / 20.0))
+}
+
+/// Adds `source` scaled by `gain` into `target`, which grows when the source is longer
+pub fn mix_into(target: &mut AudioBuffer, source: &AudioBuffer, gain: f64) -> Result<(), String> {
+    if target.channels.len() != source.channels.len() {
+        return Err("Signals with different amounts of channels can't be summed".to_string());
+    }
+
+    for (channel, source_channel) in target.channels.iter_mut().zip(source.channels.iter()) {
+        if source_channel.len() > channel.len() {
+            channel.resize(source_channel.len(), 0.0);
+        }
+        for (sample, source_sample) in channel.iter_mut().zip(source_channel.iter()) {
+            *sample += source_sample * gain;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the signal into the bus, creating it on the first send
+pub fn send(buses: &mut Buses, bus: String, buffer: &AudioBuffer, gain: f64) -> Result<(), String> {
+    let target = buses.entry(bus).or_insert_with(|| AudioBuffer { spec: buffer.spec, channels: vec![Vec::new(); buffer.channels.len()] });
+    mix_into(target, buffer, gain)
+}
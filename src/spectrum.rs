@@ -0,0 +1,31 @@
+use core::f64;
+use rustfft::{FftPlanner, num_complex::Complex};
+
+pub fn hann_window(size: usize) -> Vec<f64> {
+    (0..size).map(|n| 0.5 - 0.5 * (2.0 * f64::consts::PI * n as f64 / size as f64).cos()).collect()
+}
+
+/// Short-time Fourier transform magnitudes, one frame per `hop` samples.
+/// Magnitudes are scaled so that a full-scale sine peaks at 1.0.
+pub fn stft(channel: &[f64], fft_size: usize, hop: usize) -> Vec<Vec<f64>> {
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(fft_size);
+    let window = hann_window(fft_size);
+    let scale = 2.0 / window.iter().sum::<f64>();
+
+    let frame_count = channel.len().div_ceil(hop).max(1);
+    let mut frames = Vec::with_capacity(frame_count);
+    let mut scratch = vec![Complex::new(0.0, 0.0); fft_size];
+
+    for frame in 0..frame_count {
+        let start = frame * hop;
+        for (i, value) in scratch.iter_mut().enumerate() {
+            let sample = channel.get(start + i).copied().unwrap_or(0.0);
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut scratch);
+        frames.push(scratch[..fft_size / 2 + 1].iter().map(|c| c.norm() * scale).collect());
+    }
+
+    frames
+}
@@ -1,12 +1,64 @@
 use hound::WavSpec;
 use std::collections::HashMap;
 
+use crate::automation;
+
 
 pub const I24_MAX: i32 = 8388607;
 pub trait AudioEffect {
     fn get_name(&self) -> String;
     fn validate_arguments(&self, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<(), String>;
     fn apply_effect(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, tail_length: &Option<f64>) -> Result<Option<String>, String>;
+
+    /// Linear effects that are made of biquad sections can expose them, so their response can be computed analytically
+    fn get_biquads(&self, _arguments: &HashMap<String, f64>, _sample_rate: u32) -> Option<Vec<BiquadCoefficients>> {
+        None
+    }
+
+    /// Words accepted by an argument (like `type=butterworth`), they are passed to the effect as their index in this list
+    fn get_keywords(&self, _argument: &str) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Arguments that accept a time with a unit (`1.5s`, `250ms` or `48000smp`), bare numbers are seconds
+    fn get_time_arguments(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Numeric arguments that accept an envelope (`db=env(0s:-inf,2s:0)`), all others are rejected when automated
+    fn is_automatable(&self, _argument: &str) -> bool {
+        false
+    }
+
+    /// Same as `apply_effect`, with a value for every sample of each automated argument.
+    /// The static value of an automated argument is its first breakpoint. By default the effect is applied in short
+    /// blocks with the values at their start, which only suits effects that process every sample on its own.
+    /// Effects that carry a state from one sample to the next (filters, delays, oscillators) read the values themselves.
+    fn apply_automated(&self, buffer: &mut AudioBuffer, arguments: &HashMap<String, f64>, automation: &Automation, tail_length: &Option<f64>) -> Result<Option<String>, String> {
+        automation::apply_in_blocks(self, buffer, arguments, automation, tail_length)
+    }
+
+    /// Effects that change the timing of the signal, rather than only adding a tail. Their output replaces
+    /// the region they are restricted to instead of being mixed over it, and it can't be mixed with the dry signal
+    /// or restricted to some of the channels, which would then no longer line up.
+    fn changes_duration(&self) -> bool {
+        false
+    }
+
+    /// Samples the processed signal lags behind the input, it is moved back by as much when mixed with the dry signal
+    fn get_latency(&self, _arguments: &HashMap<String, f64>, _sample_rate: u32) -> usize {
+        0
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct BiquadCoefficients {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a0: f64,
+    pub a1: f64,
+    pub a2: f64,
 }
 
 pub struct AudioBuffer {
@@ -16,5 +68,62 @@ pub struct AudioBuffer {
 
 pub struct EffectSpec {
     pub name: String,
-    pub arguments: HashMap<String, f64>
+    pub arguments: HashMap<String, f64>,
+    pub keywords: HashMap<String, String>,
+    pub times: HashMap<String, TimeValue>,
+    pub envelopes: HashMap<String, Envelope>,
+    /// Dry/wet blend from the universal `mix` argument, handled by the chain runner
+    pub mix: Option<f64>,
+    /// Part of the signal the effect is restricted to, from an `@` modifier
+    pub target: Option<ChannelTarget>,
+    /// Time range the effect is restricted to, from a `[start..end]` suffix
+    pub region: Option<Region>
+}
+
+/// A time argument written with a unit, resolved by the chain
+#[derive(Clone, Copy)]
+pub enum TimeValue {
+    Seconds(f64),
+    Samples(f64),
+}
+
+/// Breakpoints of an argument that changes over time, values are interpolated linearly in between
+pub struct Envelope {
+    pub points: Vec<(TimeValue, f64)>,
+}
+
+/// Values of the automated arguments of an effect, one per sample
+pub type Automation = HashMap<String, Vec<f64>>;
+
+/// `name@ch=0,1` processes only the listed channels, `name@mid` and `name@side` process
+/// one half of the mid/side encoded stereo signal
+#[derive(Clone, PartialEq)]
+pub enum ChannelTarget {
+    Channels(Vec<usize>),
+    Mid,
+    Side,
+}
+
+/// `name[1.5s..3s]` processes only part of the signal in time, a missing bound means the start or end of the audio
+pub struct Region {
+    pub start: Option<RegionBound>,
+    pub end: Option<RegionBound>,
+}
+
+pub enum RegionBound {
+    Time(TimeValue),
+    /// Name of a cue point (marker) of the input file, replaced by its position once the file is read
+    Cue(String),
+}
+
+/// Cue point positions in samples by name
+pub type Cues = HashMap<String, usize>;
+
+/// An element of the effects chain
+pub enum ChainNode {
+    Effect(EffectSpec),
+    /// A spec followed by a `[ chain | chain | ... ]` group, like `multiband`
+    Group(EffectSpec, Vec<Vec<ChainNode>>),
+    /// A bare group, every chain gets a copy of the signal and the results are summed
+    Parallel(Vec<Vec<ChainNode>>),
 }
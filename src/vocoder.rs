@@ -0,0 +1,253 @@
+use core::f64;
+use rustfft::{FftPlanner, num_complex::Complex};
+
+use crate::spectrum::hann_window;
+
+/// Phase vocoder frames are the power of two closest to this length (2048 samples at 44.1 and 48 kHz)
+const FRAME_SECONDS: f64 = 0.046;
+/// Frames overlap by 75% on the side where they are closest together
+const OVERLAP: usize = 4;
+/// Spectral envelopes keep the cepstrum up to this quefrency, which is below the pitch period of most voices
+const FORMANT_QUEFRENCY_SECONDS: f64 = 0.0015;
+const WSOLA_FRAME_SECONDS: f64 = 0.04;
+/// How far WSOLA may move a frame away from its nominal position to line it up with the previous one
+const WSOLA_TOLERANCE_SECONDS: f64 = 0.01;
+/// Only every so many samples are compared when WSOLA searches for the best match
+const WSOLA_STRIDE: usize = 4;
+const RESAMPLE_HALF_TAPS: f64 = 16.0;
+
+fn frame_size(sample_rate: u32) -> usize {
+    1 << (FRAME_SECONDS * sample_rate as f64).log2().round() as u32
+}
+
+fn wrap_phase(phase: f64) -> f64 {
+    phase - f64::consts::TAU * (phase / f64::consts::TAU).round()
+}
+
+/// Log magnitudes smoothed by keeping only the low quefrencies of the cepstrum
+fn spectral_envelope(magnitude: &[f64], lifter: usize, fft: &dyn rustfft::Fft<f64>, ifft: &dyn rustfft::Fft<f64>) -> Vec<f64> {
+    let size = (magnitude.len() - 1) * 2;
+    let mut cepstrum: Vec<Complex<f64>> = (0..size).map(|k| {
+        let bin = if k < magnitude.len() { k } else { size - k };
+        Complex::new((magnitude[bin] + 1e-12).ln(), 0.0)
+    }).collect();
+
+    ifft.process(&mut cepstrum);
+    for (n, value) in cepstrum.iter_mut().enumerate() {
+        if n > lifter && n < size - lifter {
+            *value = Complex::new(0.0, 0.0);
+        }
+    }
+    fft.process(&mut cepstrum);
+
+    cepstrum[..magnitude.len()].iter().map(|c| c.re / size as f64).collect()
+}
+
+/// Bins that are larger than their two neighbours on each side
+fn find_peaks(magnitude: &[f64]) -> Vec<usize> {
+    let peaks: Vec<usize> = (0..magnitude.len()).filter(|k| {
+        let low = k.saturating_sub(2);
+        let high = (k + 2).min(magnitude.len() - 1);
+        (low..=high).all(|j| j == *k || magnitude[j] < magnitude[*k])
+    }).collect();
+
+    match peaks.is_empty() {
+        true => vec![0],
+        false => peaks,
+    }
+}
+
+/// Stretches the channel to `ratio` times its length with a phase vocoder. Only the phases of spectral peaks are
+/// advanced, the bins around a peak keep their phase relative to it (identity phase locking), which keeps partials
+/// coherent and avoids most of the usual phasiness.
+/// With a `formant_shift`, the spectral envelope of every frame is moved so that it ends up back in place
+/// once the stretched signal is resampled by that factor.
+pub fn phase_vocoder(channel: &[f64], ratio: f64, formant_shift: Option<f64>, sample_rate: u32) -> Vec<f64> {
+    let size = frame_size(sample_rate);
+    let half = size / 2;
+    let bins = half + 1;
+    let length = channel.len();
+    let output_length = (length as f64 * ratio).round() as usize;
+
+    // The closer frames are the more accurate the phase estimates, so the larger hop is kept at a quarter frame
+    let synthesis_hop = ((size / OVERLAP) as f64 * ratio.min(1.0)).round().max(1.0) as usize;
+
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(size);
+    let ifft = planner.plan_fft_inverse(size);
+    let window = hann_window(size);
+    let lifter = (FORMANT_QUEFRENCY_SECONDS * sample_rate as f64).round() as usize;
+    let omega: Vec<f64> = (0..bins).map(|k| f64::consts::TAU * k as f64 / size as f64).collect();
+
+    let mut spectrum = vec![Complex::new(0.0, 0.0); size];
+    let mut previous_phase = vec![0.0; bins];
+    let mut synthesis_phase = vec![0.0; bins];
+    let mut previous_position = 0;
+    let mut output = vec![0.0; output_length];
+    let mut normalization = vec![0.0; output_length];
+
+    // Frames are centered on their position, so the output starts in line with the input
+    for frame in 0..=output_length.div_ceil(synthesis_hop) {
+        let center = frame * synthesis_hop;
+        let position = (center as f64 / ratio).round() as isize;
+
+        for (i, value) in spectrum.iter_mut().enumerate() {
+            let index = position + i as isize - half as isize;
+            let sample = if index >= 0 && (index as usize) < length { channel[index as usize] } else { 0.0 };
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut spectrum);
+
+        let mut magnitude: Vec<f64> = spectrum[..bins].iter().map(|c| c.norm()).collect();
+        let phase: Vec<f64> = spectrum[..bins].iter().map(|c| c.arg()).collect();
+
+        if frame == 0 {
+            synthesis_phase.copy_from_slice(&phase);
+        } else {
+            let hop = (position - previous_position) as f64;
+            let peaks = find_peaks(&magnitude);
+            let mut locked = vec![0.0; bins];
+
+            for peak in peaks.iter() {
+                let deviation = wrap_phase(phase[*peak] - previous_phase[*peak] - omega[*peak] * hop);
+                locked[*peak] = synthesis_phase[*peak] + synthesis_hop as f64 * (omega[*peak] + deviation / hop);
+            }
+
+            // Every bin follows the closest peak, the boundaries lie halfway between peaks
+            let mut nearest = 0;
+            for k in 0..bins {
+                while nearest + 1 < peaks.len() && peaks[nearest + 1].abs_diff(k) < peaks[nearest].abs_diff(k) {
+                    nearest += 1;
+                }
+                let peak = peaks[nearest];
+                locked[k] = locked[peak] + phase[k] - phase[peak];
+            }
+            synthesis_phase = locked;
+        }
+        previous_phase = phase;
+        previous_position = position;
+
+        if let Some(shift) = formant_shift {
+            let envelope = spectral_envelope(&magnitude, lifter, fft.as_ref(), ifft.as_ref());
+            for (k, value) in magnitude.iter_mut().enumerate() {
+                let source = (k as f64 * shift).min(half as f64);
+                let below = source.floor() as usize;
+                let above = (below + 1).min(half);
+                let target = envelope[below] + (envelope[above] - envelope[below]) * source.fract();
+                *value *= (target - envelope[k]).exp();
+            }
+        }
+
+        for k in 0..bins {
+            spectrum[k] = Complex::from_polar(magnitude[k], synthesis_phase[k]);
+        }
+        for k in 1..half {
+            spectrum[size - k] = spectrum[k].conj();
+        }
+        ifft.process(&mut spectrum);
+
+        for (i, value) in spectrum.iter().enumerate() {
+            let index = center as isize + i as isize - half as isize;
+            if index >= 0 && (index as usize) < output_length {
+                output[index as usize] += value.re / size as f64 * window[i];
+                normalization[index as usize] += window[i] * window[i];
+            }
+        }
+    }
+
+    for (sample, weight) in output.iter_mut().zip(normalization.iter()) {
+        if *weight > 1e-9 {
+            *sample /= weight;
+        }
+    }
+
+    output
+}
+
+/// Stretches the channels to `ratio` times their length by overlapping frames of the original signal (WSOLA).
+/// Every frame is moved within a small tolerance to where it best continues the previous one, which keeps the
+/// waveform intact and suits speech. The search runs on the channel average, so the channels stay in sync.
+pub fn wsola(channels: &[Vec<f64>], ratio: f64, sample_rate: u32) -> Vec<Vec<f64>> {
+    let size = ((WSOLA_FRAME_SECONDS * sample_rate as f64).round() as usize / 2 * 2).max(2);
+    let hop = size / 2;
+    let tolerance = (WSOLA_TOLERANCE_SECONDS * sample_rate as f64).round() as isize;
+    let window = hann_window(size);
+    let length = channels[0].len();
+    let output_length = (length as f64 * ratio).round() as usize;
+
+    let mono: Vec<f64> = (0..length).map(|n| channels.iter().map(|channel| channel[n]).sum::<f64>() / channels.len() as f64).collect();
+    let sample = |index: isize| if index >= 0 && (index as usize) < length { mono[index as usize] } else { 0.0 };
+
+    let mut output = vec![vec![0.0; output_length]; channels.len()];
+    let mut normalization = vec![0.0; output_length];
+    let mut previous = 0;
+
+    for frame in 0..output_length.div_ceil(hop) {
+        let nominal = (frame as f64 * hop as f64 / ratio).round() as isize;
+
+        // The natural continuation is what followed the previous frame, the candidate that resembles it most wins
+        let position = match frame {
+            0 => 0,
+            _ => {
+                let continuation = previous + hop as isize;
+                let mut best = (f64::MIN, nominal);
+                for candidate in (nominal - tolerance).max(0)..=nominal + tolerance {
+                    let (mut correlation, mut energy) = (0.0, 0.0);
+                    for j in (0..hop as isize).step_by(WSOLA_STRIDE) {
+                        let value = sample(candidate + j);
+                        correlation += value * sample(continuation + j);
+                        energy += value * value;
+                    }
+                    let score = correlation / (energy.sqrt() + 1e-12);
+                    if score > best.0 {
+                        best = (score, candidate);
+                    }
+                }
+                best.1
+            },
+        };
+        previous = position;
+
+        let start = frame * hop;
+        for (j, gain) in window.iter().enumerate().take(output_length.saturating_sub(start)) {
+            let index = position as usize + j;
+            for (output_channel, channel) in output.iter_mut().zip(channels.iter()) {
+                output_channel[start + j] += channel.get(index).copied().unwrap_or(0.0) * gain;
+            }
+            normalization[start + j] += gain;
+        }
+    }
+
+    for channel in output.iter_mut() {
+        for (sample, weight) in channel.iter_mut().zip(normalization.iter()) {
+            if *weight > 1e-9 {
+                *sample /= weight;
+            }
+        }
+    }
+
+    output
+}
+
+/// Reads the channel `step` samples at a time with a windowed sinc interpolator. When reading faster than
+/// the original rate, the signal is low-passed below the new Nyquist frequency first.
+pub fn resample(channel: &[f64], step: f64, length: usize) -> Vec<f64> {
+    let cutoff = (1.0 / step).min(1.0);
+    let half_width = RESAMPLE_HALF_TAPS / cutoff;
+    if channel.is_empty() {
+        return vec![0.0; length];
+    }
+
+    (0..length).map(|n| {
+        let t = n as f64 * step;
+        let first = (t - half_width).ceil().max(0.0) as usize;
+        let last = ((t + half_width).floor() as usize).min(channel.len().saturating_sub(1));
+
+        (first..=last).map(|k| {
+            let x = t - k as f64;
+            let sinc = if x == 0.0 { 1.0 } else { (f64::consts::PI * x * cutoff).sin() / (f64::consts::PI * x * cutoff) };
+            let window = 0.5 + 0.5 * (f64::consts::PI * x / half_width).cos();
+            channel[k] * sinc * cutoff * window
+        }).sum()
+    }).collect()
+}